@@ -0,0 +1,105 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of lox-syntax
+
+//! Compares scanning into owned tokens with scanning into borrowed ones on
+//! a large generated file, counting heap allocations with a wrapping global
+//! allocator. Run with `cargo bench -p lox-syntax`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use lox_syntax::{BorrowedScanner, Interner, Scanner};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        // SAFETY: forwarded unchanged from our caller.
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: forwarded unchanged from our caller.
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        // SAFETY: forwarded unchanged from our caller.
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const RUNS: usize = 5;
+
+/// About 1MB of code with plenty of identifiers, numbers and operators.
+fn source() -> String {
+    let chunk = "/* block */ total = total + price_per_item * (count - 1) / 2.5;\n\
+                 print \"subtotal\" + label; // line comment\n\
+                 if (total >= limit and !done) discount = 0x1F + 0b1010 + rate;\n";
+    chunk.repeat(1_000_000 / chunk.len())
+}
+
+/// The allocations, bytes allocated and best time of `RUNS` runs of `scan`,
+/// which returns how many tokens it produced.
+fn measure(mut scan: impl FnMut() -> usize) -> (usize, usize, usize, Duration) {
+    let mut best = Duration::MAX;
+    let mut counts = (0, 0, 0);
+    for _ in 0..RUNS {
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+        let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+        let start = Instant::now();
+        let tokens = black_box(scan());
+        best = best.min(start.elapsed());
+        counts = (
+            tokens,
+            ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+            ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes,
+        );
+    }
+    (counts.0, counts.1, counts.2, best)
+}
+
+fn report(name: &str, (tokens, allocations, bytes, time): (usize, usize, usize, Duration)) {
+    println!(
+        "{name:<10} {tokens:>9} tokens {allocations:>9} allocations {:>8.1} MB {:>8.2} ms",
+        bytes as f64 / 1e6,
+        time.as_secs_f64() * 1e3
+    );
+}
+
+fn main() {
+    let source = source();
+    println!("scanning {} bytes", source.len());
+
+    report(
+        "owned",
+        measure(|| {
+            let tokens = Scanner::borrowed(&source).scan_tokens().unwrap();
+            tokens.len()
+        }),
+    );
+    report(
+        "borrowed",
+        measure(|| {
+            let mut interner = Interner::new();
+            let tokens = BorrowedScanner::new(&source, &mut interner)
+                .scan_tokens()
+                .unwrap();
+            tokens.len()
+        }),
+    );
+}
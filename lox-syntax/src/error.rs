@@ -0,0 +1,363 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of lox-syntax
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::error_code::ErrorCode;
+use crate::json;
+use crate::span::Span;
+use crate::{Token, TokenType};
+
+/// Which phase of running a program produced an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Reading or writing a file, or decoding a compiled chunk. These
+    /// errors have no source location.
+    Io,
+    Scan,
+    Parse,
+    Resolve,
+    Runtime,
+    /// A finding from static analysis. The program can still run.
+    Warning,
+}
+
+impl ErrorKind {
+    /// The `kind` written in JSON diagnostics.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Io => "io",
+            Self::Scan => "scan",
+            Self::Parse => "parse",
+            Self::Resolve => "resolve",
+            Self::Runtime => "runtime",
+            Self::Warning => "warning",
+        }
+    }
+}
+
+/// One active call at the time of a runtime error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackFrame {
+    /// Name of the function, or `None` for top-level code.
+    pub function: Option<String>,
+    pub line: usize,
+}
+
+impl StackFrame {
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"function\":{},\"line\":{}}}",
+            json::optional_string(self.function.as_deref()),
+            self.line
+        )
+    }
+}
+
+impl Display for StackFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.function {
+            Some(name) => write!(f, "[line {}] in {}()", self.line, name),
+            None => write!(f, "[line {}] in script", self.line),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoxError {
+    kind: ErrorKind,
+    code: ErrorCode,
+    span: Span,
+    // `None` when the error isn't tied to a token, empty at the end of input.
+    lexeme: Option<Box<str>>,
+    message: String,
+    // A suggested fix, e.g. the keyword a misspelled identifier was probably
+    // meant to be.
+    help: Option<Box<str>>,
+    // Innermost frame first; only runtime errors have one.
+    trace: Vec<StackFrame>,
+}
+
+impl LoxError {
+    pub fn io(code: ErrorCode, message: &str) -> Self {
+        Self::at_span(ErrorKind::Io, code, Span::default(), message)
+    }
+
+    pub fn scan(code: ErrorCode, span: Span, message: &str) -> Self {
+        Self::at_span(ErrorKind::Scan, code, span, message)
+    }
+
+    pub fn parse(code: ErrorCode, token: &Token, message: &str) -> Self {
+        Self::at_token(ErrorKind::Parse, code, token, message)
+    }
+
+    pub fn resolve(code: ErrorCode, token: &Token, message: &str) -> Self {
+        Self::at_token(ErrorKind::Resolve, code, token, message)
+    }
+
+    pub fn runtime(code: ErrorCode, token: &Token, message: &str) -> Self {
+        Self::at_token(ErrorKind::Runtime, code, token, message)
+    }
+
+    /// A runtime error that isn't caused by a particular token, e.g. running
+    /// out of stack while evaluating the expression at `span`.
+    pub fn runtime_at(code: ErrorCode, span: Span, message: &str) -> Self {
+        Self::at_span(ErrorKind::Runtime, code, span, message)
+    }
+
+    pub fn warning(code: ErrorCode, span: Span, message: &str) -> Self {
+        Self::at_span(ErrorKind::Warning, code, span, message)
+    }
+
+    fn at_span(kind: ErrorKind, code: ErrorCode, span: Span, message: &str) -> Self {
+        Self {
+            kind,
+            code,
+            span,
+            lexeme: None,
+            message: message.to_string(),
+            help: None,
+            trace: Vec::new(),
+        }
+    }
+
+    fn at_token(kind: ErrorKind, code: ErrorCode, token: &Token, message: &str) -> Self {
+        let lexeme = if *token.token_type() == TokenType::EOF {
+            String::new()
+        } else {
+            token.lexeme().clone()
+        };
+
+        Self {
+            kind,
+            code,
+            span: token.span(),
+            lexeme: Some(lexeme.into_boxed_str()),
+            message: message.to_string(),
+            help: None,
+            trace: Vec::new(),
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    pub fn line(&self) -> usize {
+        self.span.line
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn lexeme(&self) -> Option<&str> {
+        self.lexeme.as_deref()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn help(&self) -> Option<&str> {
+        self.help.as_deref()
+    }
+
+    pub fn with_help(mut self, help: String) -> Self {
+        self.help = Some(help.into_boxed_str());
+        self
+    }
+
+    /// The calls that were active when a runtime error happened, innermost first.
+    pub fn trace(&self) -> &[StackFrame] {
+        &self.trace
+    }
+
+    pub fn with_trace(mut self, trace: Vec<StackFrame>) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// The error followed by the offending source line with the range it
+    /// refers to underlined, and where to read more about it. `program` is
+    /// the binary whose `--explain` the hint points at.
+    pub fn render(&self, source: &str, program: &str) -> String {
+        if self.kind == ErrorKind::Io {
+            return self.to_string();
+        }
+        let help = match self.help.as_deref() {
+            Some(help) => format!("help: {}\n", help),
+            None => String::new(),
+        };
+        format!(
+            "{}\n{}\n{}For more information about this {}, try `{} --explain {}`.",
+            self,
+            self.span.render(source),
+            help,
+            if self.kind == ErrorKind::Warning {
+                "warning"
+            } else {
+                "error"
+            },
+            program,
+            self.code
+        )
+    }
+
+    /// The error as a single line JSON object, for editors and CI.
+    pub fn to_json(&self, file: Option<&str>) -> String {
+        let trace = self
+            .trace
+            .iter()
+            .map(StackFrame::to_json)
+            .collect::<Vec<_>>()
+            .join(",");
+        let location = if self.kind == ErrorKind::Io {
+            "\"line\":null,\"column\":null,\"span\":null".to_string()
+        } else {
+            format!(
+                "\"line\":{},\"column\":{},\"span\":{{\"start\":{},\"end\":{}}}",
+                self.span.line, self.span.column, self.span.start, self.span.end
+            )
+        };
+
+        let severity = if self.kind == ErrorKind::Warning {
+            "warning"
+        } else {
+            "error"
+        };
+
+        format!(
+            "{{\"severity\":\"{}\",\"kind\":\"{}\",\"code\":\"{}\",\"message\":{},\"file\":{},{},\"help\":{},\"trace\":[{}]}}",
+            severity,
+            self.kind.name(),
+            self.code,
+            json::string(&self.message),
+            json::optional_string(file),
+            location,
+            json::optional_string(self.help.as_deref()),
+            trace
+        )
+    }
+}
+
+impl Display for LoxError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if matches!(self.kind, ErrorKind::Io | ErrorKind::Runtime) {
+            write!(f, "{}", self.message)?;
+            for frame in &self.trace {
+                write!(f, "\n{}", frame)?;
+            }
+            return Ok(());
+        }
+
+        let location = match self.lexeme.as_deref() {
+            None => String::new(),
+            Some("") => " at end".to_string(),
+            Some(lexeme) => format!(" at '{}'", lexeme),
+        };
+        let severity = if self.kind == ErrorKind::Warning {
+            "Warning"
+        } else {
+            "Error"
+        };
+        write!(
+            f,
+            "[line {}] {}{}: {}",
+            self.span.line, severity, location, self.message
+        )
+    }
+}
+
+impl std::error::Error for LoxError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json() {
+        let token = Token::new(
+            TokenType::SEMICOLON,
+            ";".to_string(),
+            None,
+            Span::new(9, 10, 1, 10),
+        );
+        let error = LoxError::parse(ErrorCode::L0003, &token, "Expect \"expression\".");
+
+        assert_eq!(
+            error.to_json(Some("dir/a.lox")),
+            "{\"severity\":\"error\",\"kind\":\"parse\",\"code\":\"L0003\",\
+             \"message\":\"Expect \\\"expression\\\".\",\"file\":\"dir/a.lox\",\
+             \"line\":1,\"column\":10,\"span\":{\"start\":9,\"end\":10},\"help\":null,\"trace\":[]}"
+        );
+    }
+
+    #[test]
+    fn test_runtime_error_to_json() {
+        let token = Token::new(
+            TokenType::MINUS,
+            "-".to_string(),
+            None,
+            Span::new(6, 7, 2, 7),
+        );
+        let error = LoxError::runtime(ErrorCode::L0006, &token, "Operand must be a number.")
+            .with_trace(vec![StackFrame {
+                function: None,
+                line: 2,
+            }]);
+
+        assert!(
+            error
+                .to_json(None)
+                .ends_with("\"file\":null,\"line\":2,\"column\":7,\"span\":{\"start\":6,\"end\":7},\"help\":null,\"trace\":[{\"function\":null,\"line\":2}]}")
+        );
+    }
+
+    #[test]
+    fn test_io_error_has_no_location() {
+        let error = LoxError::io(ErrorCode::L0019, "Could not read \"a.lox\".");
+
+        assert_eq!(error.to_string(), "Could not read \"a.lox\".");
+        assert_eq!(error.render("", "rlox-ast"), error.to_string());
+        assert_eq!(
+            error.to_json(Some("a.lox")),
+            "{\"severity\":\"error\",\"kind\":\"io\",\"code\":\"L0019\",\
+             \"message\":\"Could not read \\\"a.lox\\\".\",\"file\":\"a.lox\",\
+             \"line\":null,\"column\":null,\"span\":null,\"help\":null,\"trace\":[]}"
+        );
+    }
+
+    #[test]
+    fn test_render_points_at_the_running_binary() {
+        let source = "print 1 +;";
+        let token = Token::new(
+            TokenType::SEMICOLON,
+            ";".to_string(),
+            None,
+            Span::new(9, 10, 1, 10),
+        );
+        let error = LoxError::parse(ErrorCode::L0003, &token, "Expect expression.");
+
+        assert_eq!(
+            error.render(source, "rlox-bytecode"),
+            "[line 1] Error at ';': Expect expression.\n\
+             \x201 | print 1 +;\n\
+             \x20  |          ^\n\
+             For more information about this error, try `rlox-bytecode --explain L0003`."
+        );
+
+        let warning = LoxError::warning(ErrorCode::L0021, Span::new(6, 7, 1, 7), "Unused.");
+        assert!(
+            warning.render(source, "rlox-ast").ends_with(
+                "For more information about this warning, try `rlox-ast --explain L0021`."
+            )
+        );
+    }
+}
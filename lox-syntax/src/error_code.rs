@@ -0,0 +1,382 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of lox-syntax
+
+use std::fmt::{self, Display, Formatter};
+
+/// A stable identifier for each kind of error, so an error can be looked up
+/// with `rlox-ast --explain` and linked to. Codes are never renumbered or
+/// reused; new errors get the next free number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// Unterminated string.
+    L0001,
+    /// Unexpected character.
+    L0002,
+    /// Expected an expression.
+    L0003,
+    /// Missing `)` after a parenthesized expression.
+    L0004,
+    /// Missing `;` after a statement.
+    L0005,
+    /// Operand of a unary operator is not a number.
+    L0006,
+    /// Operands of an arithmetic or comparison operator are not numbers.
+    L0007,
+    /// Operands of `+` are not two numbers or two strings.
+    L0008,
+    /// An expression is nested deeper than the parser allows.
+    L0009,
+    /// Evaluation went deeper than the interpreter allows.
+    L0010,
+    /// Unknown escape sequence in a string.
+    L0011,
+    /// Malformed `\u{...}` escape or invalid code point.
+    L0012,
+    /// A `${` in a string is never closed.
+    L0013,
+    /// Something other than `}` follows an interpolated expression.
+    L0014,
+    /// A number literal is malformed or out of range.
+    L0015,
+    /// A `/*` comment is never closed.
+    L0016,
+    /// Something follows a complete expression in compiled source.
+    L0017,
+    /// The source uses something the bytecode compiler can't compile yet.
+    L0018,
+    /// A file couldn't be read, or isn't a valid `.loxc` chunk.
+    L0019,
+    /// A chunk fails verification or faults in the VM.
+    L0020,
+    /// An expression statement's value is never used.
+    L0021,
+    /// An output file couldn't be written.
+    L0022,
+}
+
+struct Explanation {
+    title: &'static str,
+    description: &'static str,
+    example: &'static str,
+    fix: &'static str,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 22] = [
+        Self::L0001,
+        Self::L0002,
+        Self::L0003,
+        Self::L0004,
+        Self::L0005,
+        Self::L0006,
+        Self::L0007,
+        Self::L0008,
+        Self::L0009,
+        Self::L0010,
+        Self::L0011,
+        Self::L0012,
+        Self::L0013,
+        Self::L0014,
+        Self::L0015,
+        Self::L0016,
+        Self::L0017,
+        Self::L0018,
+        Self::L0019,
+        Self::L0020,
+        Self::L0021,
+        Self::L0022,
+    ];
+
+    /// Looks up a code by its name, e.g. `"L0001"`.
+    pub fn from_name(name: &str) -> Option<ErrorCode> {
+        Self::ALL
+            .into_iter()
+            .find(|code| code.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::L0001 => "L0001",
+            Self::L0002 => "L0002",
+            Self::L0003 => "L0003",
+            Self::L0004 => "L0004",
+            Self::L0005 => "L0005",
+            Self::L0006 => "L0006",
+            Self::L0007 => "L0007",
+            Self::L0008 => "L0008",
+            Self::L0009 => "L0009",
+            Self::L0010 => "L0010",
+            Self::L0011 => "L0011",
+            Self::L0012 => "L0012",
+            Self::L0013 => "L0013",
+            Self::L0014 => "L0014",
+            Self::L0015 => "L0015",
+            Self::L0016 => "L0016",
+            Self::L0017 => "L0017",
+            Self::L0018 => "L0018",
+            Self::L0019 => "L0019",
+            Self::L0020 => "L0020",
+            Self::L0021 => "L0021",
+            Self::L0022 => "L0022",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        self.explanation().title
+    }
+
+    /// The long-form explanation printed by `rlox-ast --explain`.
+    pub fn explain(&self) -> String {
+        let explanation = self.explanation();
+        format!(
+            "{} {}\n\n{}\n\nErroneous code example:\n\n{}\n\nFixed:\n\n{}\n",
+            self.name(),
+            explanation.title,
+            explanation.description,
+            indent(explanation.example),
+            indent(explanation.fix)
+        )
+    }
+
+    fn explanation(&self) -> Explanation {
+        match self {
+            Self::L0001 => Explanation {
+                title: "Unterminated string",
+                description: "A string literal was opened with `\"` but the file ended before \
+                              the closing `\"`. Strings may span several lines, so the \
+                              missing quote can be far above where the file ends.",
+                example: "print \"hello;",
+                fix: "print \"hello\";",
+            },
+            Self::L0002 => Explanation {
+                title: "Unexpected character",
+                description: "The scanner found a character that cannot start any Lox \
+                              token, such as `@`, `#` or `|`. Scanning continues after it, \
+                              so every such character is reported.",
+                example: "print 1 | 2;",
+                fix: "print 1 or 2;",
+            },
+            Self::L0003 => Explanation {
+                title: "Expected expression",
+                description: "The parser needed an expression, like a literal, a \
+                              parenthesized expression or a unary operator applied to one, \
+                              but found something else. This usually means an operand is \
+                              missing.",
+                example: "print 1 +;",
+                fix: "print 1 + 2;",
+            },
+            Self::L0004 => Explanation {
+                title: "Missing closing parenthesis",
+                description: "A `(` that groups an expression has no matching `)` after the \
+                              expression inside it.",
+                example: "print (1 + 2;",
+                fix: "print (1 + 2);",
+            },
+            Self::L0005 => Explanation {
+                title: "Missing semicolon",
+                description: "Every expression statement and `print` statement ends with \
+                              `;`. The parser reached the end of the expression and found \
+                              something else.",
+                example: "print 1\nprint 2;",
+                fix: "print 1;\nprint 2;",
+            },
+            Self::L0006 => Explanation {
+                title: "Operand must be a number",
+                description: "Unary `-` only works on numbers. Lox doesn't convert other \
+                              values to numbers implicitly.",
+                example: "print -\"3\";",
+                fix: "print -3;",
+            },
+            Self::L0007 => Explanation {
+                title: "Operands must be numbers",
+                description: "The arithmetic operators `-`, `*` and `/` and the comparison \
+                              operators `<`, `<=`, `>` and `>=` only work on two numbers.",
+                example: "print \"10\" > 9;",
+                fix: "print 10 > 9;",
+            },
+            Self::L0008 => Explanation {
+                title: "Operands must be two numbers or two strings",
+                description: "`+` adds two numbers or concatenates two strings. Mixing a \
+                              string with a number, or using any other kind of value, is an \
+                              error.",
+                example: "print \"total: \" + 3;",
+                fix: "print \"total: \" + \"3\";",
+            },
+            Self::L0009 => Explanation {
+                title: "Expression nested too deeply",
+                description: "The parser limits how deeply expressions may nest, counting \
+                              parentheses, unary operators and interpolated expressions, so \
+                              that pathological input can't exhaust the interpreter's stack. \
+                              Operators in a chain like `1 + 2 + 3` don't count. The limit can \
+                              be raised with `--max-depth`, but deep nesting is usually better \
+                              split up.",
+                example: "print ((((((((((1))))))))));  // with --max-depth=8",
+                fix: "print 1;",
+            },
+            Self::L0010 => Explanation {
+                title: "Stack overflow",
+                description: "Parentheses, unary operators or interpolations were nested deeper \
+                              than the interpreter's depth limit, which keeps it from crashing \
+                              when it runs out of native stack. Chains \
+                              like `1 + 2 + 3` don't count. rlox gives the parser the same \
+                              limit, so a script normally stops at L0009 first; this error \
+                              comes from syntax trees evaluated with a lower limit than they \
+                              were parsed with.",
+                example: "print -(-(-(-(-(-(-(-(-(-1)))))))));  // with a depth limit of 8",
+                fix: "print -1;",
+            },
+            Self::L0011 => Explanation {
+                title: "Unknown escape sequence",
+                description: "Inside a string, `\\` starts an escape sequence. The escapes Lox \
+                              understands are `\\n`, `\\t`, `\\r`, `\\\\`, `\\\"`, `\\0`, `\\$` \
+                              and `\\u{...}`. There are no line continuations, so a `\\` at the end \
+                              of a line is unknown too. To write a backslash itself, escape it \
+                              as `\\\\`.",
+                example: "print \"C:\\data\";",
+                fix: "print \"C:\\\\data\";",
+            },
+            Self::L0012 => Explanation {
+                title: "Invalid Unicode escape",
+                description: "A `\\u` escape must be followed by 1 to 6 hexadecimal digits in \
+                              braces, and the number must be a Unicode scalar value: at most \
+                              10FFFF and not a surrogate (D800 to DFFF).",
+                example: "print \"\\u1F600 \\u{D800}\";",
+                fix: "print \"\\u{1F600}\";",
+            },
+            Self::L0013 => Explanation {
+                title: "Unterminated string interpolation",
+                description: "`${` inside a string starts an interpolated expression, which \
+                              runs until the matching `}`. The file ended before that `}`. \
+                              To write a literal `${`, escape the dollar sign as `\\$`.",
+                example: "print \"total: ${1 + 2\";",
+                fix: "print \"total: ${1 + 2}\";",
+            },
+            Self::L0014 => Explanation {
+                title: "Expected '}' after interpolated expression",
+                description: "An interpolated expression in a string must be a single \
+                              expression followed by `}`. The parser finished the expression \
+                              and found something else.",
+                example: "print \"sum: ${1 2}\";",
+                fix: "print \"sum: ${1 + 2}\";",
+            },
+            Self::L0015 => Explanation {
+                title: "Malformed number literal",
+                description: "Numbers are written in decimal, optionally with a fraction and \
+                              an exponent (`6.02e23`), or as integers in hexadecimal (`0xFF`), \
+                              octal (`0o17`) or binary (`0b1010`). `_` may go between two \
+                              digits, as in `1_000`. A prefix or exponent must be followed by \
+                              at least one digit valid for it, and the value must fit in a \
+                              number.",
+                example: "print 0x + 1e;",
+                fix: "print 0x10 + 1e3;",
+            },
+            Self::L0016 => Explanation {
+                title: "Unterminated block comment",
+                description: "A `/*` comment runs until its matching `*/`. Block comments nest, \
+                              so each `/*` inside one needs its own `*/`, including any in \
+                              commented-out code.",
+                example: "/* outer /* inner */\nprint 1;",
+                fix: "/* outer /* inner */ */\nprint 1;",
+            },
+            Self::L0017 => Explanation {
+                title: "Expected end of expression",
+                description: "rlox-bytecode compiles a single expression, as far as the book's \
+                              compiler goes so far. Anything after that expression is an error, \
+                              including a trailing `;`.",
+                example: "1 + 2; 3",
+                fix: "1 + 2 + 3",
+            },
+            Self::L0018 => Explanation {
+                title: "Not supported by the bytecode compiler",
+                description: "The bytecode VM only has numbers and the arithmetic operators `+`, `-`, \
+                              `*` and `/` so far. Strings, `true`, `false`, `nil`, `!`, comparisons, \
+                              and more than 256 constants in one chunk are valid Lox that rlox-ast \
+                              runs but rlox-bytecode can't compile yet.",
+                example: "-(1 + 2) < 4",
+                fix: "-(1 + 2) - 4",
+            },
+            Self::L0019 => Explanation {
+                title: "Could not load input",
+                description: "The interpreter couldn't read the file it was given, or \
+                              `rlox-bytecode run` couldn't decode it as a `.loxc` chunk. A \
+                              `.loxc` file may be source code rather than a compiled chunk, come \
+                              from a version of rlox-bytecode with a different format version, \
+                              or be truncated or damaged. Compiling the source again gives a \
+                              fresh file.",
+                example: "rlox-bytecode run sum.lox",
+                fix: "rlox-bytecode compile sum.lox -o sum.loxc\nrlox-bytecode run sum.loxc",
+            },
+            Self::L0020 => Explanation {
+                title: "Invalid bytecode",
+                description: "A chunk failed verification, or the VM hit an instruction it can't \
+                              execute, such as one that pops from an empty stack. The compiler never \
+                              produces such chunks, so the code was built by hand or the file was \
+                              edited after it was compiled. Compile the source again.",
+                example: "rlox-bytecode run patched.loxc",
+                fix: "rlox-bytecode compile sum.lox -o sum.loxc\nrlox-bytecode run sum.loxc",
+            },
+            Self::L0021 => Explanation {
+                title: "Unused expression result",
+                description: "This is a warning. An expression statement evaluates its expression and \
+                              throws the value away. Lox expressions can't have side effects yet, \
+                              since there is no assignment and there are no calls, so the statement \
+                              does nothing. It is usually a `print` that was left out. \
+                              `--deny-warnings` turns this into an error.",
+                example: "1 + 2;",
+                fix: "print 1 + 2;",
+            },
+            Self::L0022 => Explanation {
+                title: "Could not write output",
+                description: "rlox-bytecode couldn't create or write a file it was asked to produce: \
+                              the `.loxc` chunk from `compile -o`, or the trace file from \
+                              `--trace=<file>`. The directory may not exist, or it may not be \
+                              writable.",
+                example: "rlox-bytecode compile sum.lox -o missing/dir/sum.loxc",
+                fix: "rlox-bytecode compile sum.lox -o sum.loxc",
+            },
+        }
+    }
+}
+
+fn indent(code: &str) -> String {
+    code.lines()
+        .map(|line| format!("    {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_are_unique_and_round_trip() {
+        for (index, code) in ErrorCode::ALL.iter().enumerate() {
+            assert_eq!(code.name(), format!("L{:04}", index + 1));
+            assert_eq!(ErrorCode::from_name(code.name()), Some(*code));
+        }
+        assert_eq!(ErrorCode::from_name("l0003"), Some(ErrorCode::L0003));
+        assert_eq!(ErrorCode::from_name("L9999"), None);
+    }
+
+    #[test]
+    fn test_explain() {
+        assert_eq!(
+            ErrorCode::L0001.explain(),
+            "L0001 Unterminated string\n\n\
+             A string literal was opened with `\"` but the file ended before the closing `\"`. \
+             Strings may span several lines, so the missing quote can be far above where the \
+             file ends.\n\n\
+             Erroneous code example:\n\n    print \"hello;\n\n\
+             Fixed:\n\n    print \"hello\";\n"
+        );
+    }
+}
@@ -0,0 +1,56 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of lox-syntax
+
+use std::collections::HashMap;
+
+/// A small number standing for an interned name. Two symbols from the same
+/// [`Interner`] are equal exactly when their names are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Maps names borrowed from the source to symbols and back, so comparing or
+/// hashing a name is comparing a number and each name is stored once.
+#[derive(Debug, Default)]
+pub struct Interner<'src> {
+    symbols: HashMap<&'src str, Symbol>,
+    names: Vec<&'src str>,
+}
+
+impl<'src> Interner<'src> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, name: &'src str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(name) {
+            return *symbol;
+        }
+        let symbol =
+            Symbol(u32::try_from(self.names.len()).expect("fewer than 2^32 distinct names"));
+        self.symbols.insert(name, symbol);
+        self.names.push(name);
+        symbol
+    }
+
+    /// The name a symbol stands for. Panics if the symbol came from another
+    /// interner.
+    pub fn resolve(&self, symbol: Symbol) -> &'src str {
+        self.names[symbol.index()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
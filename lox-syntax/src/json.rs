@@ -0,0 +1,49 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of lox-syntax
+
+//! The JSON encoding shared by every `--error-format=json` writer.
+
+use std::fmt::Write;
+
+/// Encodes `value` as a quoted JSON string.
+pub fn string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                write!(out, "\\u{:04x}", c as u32).expect("Writing to a String cannot fail")
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Encodes `value` as a JSON string, or `null` when there isn't one.
+pub fn optional_string(value: Option<&str>) -> String {
+    value.map_or("null".to_string(), string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_escapes() {
+        assert_eq!(string("plain"), "\"plain\"");
+        assert_eq!(
+            string("say \"hi\"\\\n\t\u{1}é"),
+            "\"say \\\"hi\\\"\\\\\\n\\t\\u0001é\""
+        );
+        assert_eq!(optional_string(None), "null");
+    }
+}
@@ -0,0 +1,26 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of lox-syntax
+
+//! The lexical side of Lox shared by both interpreters: the scanner, its
+//! tokens, source spans and the errors every stage reports.
+
+mod error;
+mod error_code;
+mod interner;
+pub mod json;
+mod object;
+mod scanner;
+mod span;
+mod token;
+mod token_type;
+
+pub use error::{ErrorKind, LoxError, StackFrame};
+pub use error_code::ErrorCode;
+pub use interner::{Interner, Symbol};
+pub use object::Object;
+pub use scanner::{BorrowedScanner, Scanner, TextEdit};
+pub use span::Span;
+pub use token::{BorrowedToken, Token};
+pub use token_type::TokenType;
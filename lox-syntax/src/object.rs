@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2024 John Irle
 // SPDX-License-Identifier: MIT
 //
-// This file is part of rlox-ast
+// This file is part of lox-syntax
 
 use std::fmt::{self, Display, Formatter};
 
@@ -0,0 +1,1831 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of lox-syntax
+
+use std::borrow::Cow;
+use std::iter::FusedIterator;
+use std::ops::Range;
+
+use crate::error::LoxError;
+use crate::error_code::ErrorCode;
+use crate::interner::Interner;
+use crate::object::Object;
+use crate::span::Span;
+use crate::token::BorrowedToken;
+use crate::{Token, TokenType};
+
+pub struct Scanner<'src> {
+    source: Cow<'src, str>,
+    errors: Vec<LoxError>,
+    // Byte offsets into `source`, always on a char boundary.
+    start: usize,
+    current: usize,
+    line: usize,
+    // Column of `current` in chars, so a multi-byte character counts once.
+    column: usize,
+    start_line: usize,
+    start_column: usize,
+    // One entry per `${` we are inside, counting the `{` opened within it so
+    // the `}` that ends the interpolation can be told apart.
+    interpolations: Vec<usize>,
+    // `///` comment lines waiting to be attached to the next token.
+    doc: Option<String>,
+    // Whether tokens get their surrounding whitespace and comments.
+    keep_trivia: bool,
+    // Where the source not yet handed out as part of a token begins.
+    trivia_start: usize,
+    // Whether the EOF token has been handed out, after which iteration ends.
+    done: bool,
+}
+
+/// A change to source text: the bytes in `range` are replaced by `text`.
+/// Both ends of `range` must fall on char boundaries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit<'a> {
+    pub range: Range<usize>,
+    pub text: &'a str,
+}
+
+// A token before its lexeme is copied out of or borrowed from the source.
+struct Scanned {
+    token_type: TokenType,
+    literal: Option<Object>,
+    span: Span,
+}
+
+impl<'src> Scanner<'src> {
+    pub fn new(source: String) -> Self {
+        Self::from_cow(Cow::Owned(source))
+    }
+
+    /// Scans source owned by the caller instead of taking a copy of it.
+    pub fn borrowed(source: &'src str) -> Self {
+        Self::from_cow(Cow::Borrowed(source))
+    }
+
+    fn from_cow(source: Cow<'src, str>) -> Self {
+        Self {
+            source,
+            errors: Vec::new(),
+            start: 0,
+            current: 0,
+            line: 1,
+            column: 1,
+            start_line: 1,
+            start_column: 1,
+            interpolations: Vec::new(),
+            doc: None,
+            keep_trivia: false,
+            trivia_start: 0,
+            done: false,
+        }
+    }
+
+    /// Makes every token carry the whitespace, newlines and comments around
+    /// it as leading and trailing trivia, so that writing out each token's
+    /// leading trivia, lexeme and trailing trivia in order reproduces the
+    /// source exactly. Must be set before the first token is scanned.
+    pub fn keep_trivia(&mut self, keep: bool) {
+        self.keep_trivia = keep;
+    }
+
+    /// Scans the whole source, reporting every error found rather than
+    /// stopping at the first one. Iterate over the scanner instead to get
+    /// tokens one at a time.
+    pub fn scan_tokens(&mut self) -> Result<Vec<Token>, Vec<LoxError>> {
+        let tokens: Vec<Token> = self.by_ref().collect();
+        if self.errors.is_empty() {
+            Ok(tokens)
+        } else {
+            Err(self.errors.clone())
+        }
+    }
+
+    /// The errors found in the tokens scanned so far. Lexemes that fail to
+    /// scan are skipped rather than yielded.
+    pub fn errors(&self) -> &[LoxError] {
+        &self.errors
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.current >= self.source.len()
+    }
+
+    fn advance(&mut self) -> char {
+        let character = self.source[self.current..]
+            .chars()
+            .next()
+            .expect("advance is only called before the end of the source");
+        self.current += character.len_utf8();
+        self.column += 1;
+        character
+    }
+
+    fn add_token(&self, token_type: TokenType) -> Option<Scanned> {
+        self.add_token_literal(token_type, None)
+    }
+
+    fn add_token_literal(&self, token_type: TokenType, literal: Option<Object>) -> Option<Scanned> {
+        Some(Scanned {
+            token_type,
+            literal,
+            span: self.current_span(),
+        })
+    }
+
+    /// Span of the lexeme scanned so far.
+    fn current_span(&self) -> Span {
+        Span::new(self.start, self.current, self.start_line, self.start_column)
+    }
+
+    fn newline(&mut self) {
+        self.line += 1;
+        self.column = 1;
+    }
+
+    /// Scans one lexeme, returning `None` for whitespace, comments and
+    /// errors.
+    fn scan_token(&mut self) -> Option<Scanned> {
+        let c = self.advance();
+        match c {
+            '(' => self.add_token(TokenType::LEFT_PAREN),
+            ')' => self.add_token(TokenType::RIGHT_PAREN),
+            '{' => {
+                if let Some(braces) = self.interpolations.last_mut() {
+                    *braces += 1;
+                }
+                self.add_token(TokenType::LEFT_BRACE)
+            }
+            '}' => match self.interpolations.last_mut() {
+                Some(0) => {
+                    // The end of an interpolated expression; the string resumes.
+                    self.interpolations.pop();
+                    self.string()
+                }
+                Some(braces) => {
+                    *braces -= 1;
+                    self.add_token(TokenType::RIGHT_BRACE)
+                }
+                None => self.add_token(TokenType::RIGHT_BRACE),
+            },
+            ',' => self.add_token(TokenType::COMMA),
+            '.' => self.add_token(TokenType::DOT),
+            '-' => self.add_token(TokenType::MINUS),
+            '+' => self.add_token(TokenType::PLUS),
+            ';' => self.add_token(TokenType::SEMICOLON),
+            '*' => self.add_token(TokenType::STAR),
+            '!' => {
+                if self.match_char('=') {
+                    self.add_token(TokenType::BANG_EQUAL)
+                } else {
+                    self.add_token(TokenType::BANG)
+                }
+            }
+            '=' => {
+                if self.match_char('=') {
+                    self.add_token(TokenType::EQUAL_EQUAL)
+                } else {
+                    self.add_token(TokenType::EQUAL)
+                }
+            }
+            '<' => {
+                if self.match_char('=') {
+                    self.add_token(TokenType::LESS_EQUAL)
+                } else {
+                    self.add_token(TokenType::LESS)
+                }
+            }
+            '>' => {
+                if self.match_char('=') {
+                    self.add_token(TokenType::GREATER_EQUAL)
+                } else {
+                    self.add_token(TokenType::GREATER)
+                }
+            }
+            '/' => {
+                if self.match_char('/') {
+                    while self.peek() != '\n' && !self.is_at_end() {
+                        self.advance();
+                    }
+                    self.doc_comment();
+                    None
+                } else if self.match_char('*') {
+                    self.block_comment();
+                    None
+                } else {
+                    self.add_token(TokenType::SLASH)
+                }
+            }
+            ' ' | '\r' | '\t' => None,
+            '\n' => {
+                self.newline();
+                None
+            }
+            '"' => self.string(),
+            _ => {
+                if self.is_digit(c) {
+                    self.number(c)
+                } else if self.is_alpha(c) {
+                    self.identifier()
+                } else {
+                    self.errors.push(LoxError::scan(
+                        ErrorCode::L0002,
+                        self.current_span(),
+                        "Unexpected character.",
+                    ));
+                    None
+                }
+            }
+        }
+    }
+
+    /// The text after `///` if `comment` is a doc comment. Like Rust,
+    /// `////` and longer are ordinary comments.
+    fn doc_text(comment: &str) -> Option<&str> {
+        comment
+            .strip_prefix("///")
+            .filter(|text| !text.starts_with('/'))
+    }
+
+    /// Collects the line comment just scanned if it is a doc comment.
+    fn doc_comment(&mut self) {
+        let comment = &self.source[self.start..self.current];
+        let Some(text) = Self::doc_text(comment) else {
+            return;
+        };
+
+        let text = text.strip_prefix(' ').unwrap_or(text);
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        match &mut self.doc {
+            Some(doc) => {
+                doc.push('\n');
+                doc.push_str(text);
+            }
+            None => self.doc = Some(text.to_string()),
+        }
+    }
+
+    /// Skips a `/* */` comment, which may contain other block comments.
+    fn block_comment(&mut self) {
+        let mut depth = 1;
+        while depth > 0 && !self.is_at_end() {
+            match self.advance() {
+                '/' if self.match_char('*') => depth += 1,
+                '*' if self.match_char('/') => depth -= 1,
+                '\n' => self.newline(),
+                _ => {}
+            }
+        }
+
+        if depth > 0 {
+            self.errors.push(LoxError::scan(
+                ErrorCode::L0016,
+                self.current_span(),
+                "Unterminated block comment.",
+            ));
+        }
+    }
+
+    fn attach_doc(&mut self, token: Token) -> Token {
+        match self.doc.take() {
+            Some(doc) => token.with_doc(doc),
+            None => token,
+        }
+    }
+
+    /// Gives a token the source since the previous token as leading trivia,
+    /// and takes whatever follows it on the same line, up to the next lexeme
+    /// or doc comment, as trailing trivia.
+    fn attach_trivia(&mut self, token: Token) -> Token {
+        if !self.keep_trivia {
+            return token;
+        }
+
+        let span = token.span();
+        while matches!(self.peek(), ' ' | '\t') {
+            self.advance();
+        }
+        let rest = &self.source[self.current..];
+        if rest.starts_with("//") && Self::doc_text(rest).is_none() {
+            while self.peek() != '\n' && !self.is_at_end() {
+                self.advance();
+            }
+        }
+
+        let leading = &self.source[self.trivia_start..span.start];
+        let trailing = &self.source[span.end..self.current];
+        let token = token.with_trivia(leading, trailing);
+        self.trivia_start = self.current;
+        token
+    }
+
+    fn identifier(&mut self) -> Option<Scanned> {
+        while self.is_alpha_numeric(self.peek()) {
+            self.advance();
+        }
+
+        let text = &self.source[self.start..self.current];
+        let token_type = Self::keywords(text);
+        let token_type = match token_type {
+            Some(token_type) => token_type.clone(),
+            None => TokenType::IDENTIFIER,
+        };
+        self.add_token(token_type)
+    }
+
+    fn number(&mut self, first: char) -> Option<Scanned> {
+        if first == '0' {
+            match self.peek() {
+                'x' => return self.radix_number(16, "hexadecimal"),
+                'o' => return self.radix_number(8, "octal"),
+                'b' => return self.radix_number(2, "binary"),
+                _ => {}
+            }
+        }
+
+        self.digits();
+        if self.peek() == '.' && self.is_digit(self.peek_next()) {
+            self.advance();
+            self.digits();
+        }
+        if matches!(self.peek(), 'e' | 'E') {
+            self.advance();
+            if matches!(self.peek(), '+' | '-') {
+                self.advance();
+            }
+            if self.digits() == 0 {
+                return self.number_error("Expect digits in exponent.");
+            }
+        }
+
+        let literal = &self.source[self.start..self.current];
+        if !separates_digits(literal, 10) {
+            return self.number_error("Expect digits on both sides of '_'.");
+        }
+
+        // With the separators gone the literal is valid Rust float syntax,
+        // so this parses to the same value Rust would give it.
+        let text: String = literal.chars().filter(|c| *c != '_').collect();
+        let value: f64 = text.parse().expect("Scanned a well-formed float");
+        if value.is_infinite() {
+            return self.number_error("Number literal is too large.");
+        }
+        self.add_token_literal(TokenType::NUMBER, Some(Object::Num(value)))
+    }
+
+    /// Scans a `0x`, `0o` or `0b` integer literal, after its `0`.
+    fn radix_number(&mut self, radix: u32, name: &str) -> Option<Scanned> {
+        let prefix = self.advance();
+        // Take every character that could belong to the literal, so `0b102`
+        // is reported as a bad binary digit rather than `0b10` and `2`.
+        while self.peek().is_ascii_alphanumeric() || self.peek() == '_' {
+            self.advance();
+        }
+
+        let literal = &self.source[self.start + 2..self.current];
+        let mut value: u128 = 0;
+        let mut digits = 0;
+        for c in literal.chars() {
+            if c == '_' {
+                continue;
+            }
+            let Some(digit) = c.to_digit(radix) else {
+                return self.number_error(&format!("Invalid digit '{}' in {} literal.", c, name));
+            };
+            value = match value
+                .checked_mul(radix.into())
+                .and_then(|value| value.checked_add(digit.into()))
+            {
+                Some(value) => value,
+                None => return self.number_error("Number literal is too large."),
+            };
+            digits += 1;
+        }
+        if digits == 0 {
+            return self.number_error(&format!("Expect {} digits after '0{}'.", name, prefix));
+        }
+        if !separates_digits(literal, radix) {
+            return self.number_error("Expect digits on both sides of '_'.");
+        }
+
+        self.add_token_literal(TokenType::NUMBER, Some(Object::Num(value as f64)))
+    }
+
+    /// Consumes decimal digits and `_` separators, returning how many digits
+    /// there were.
+    fn digits(&mut self) -> usize {
+        let mut count = 0;
+        while self.is_digit(self.peek()) || self.peek() == '_' {
+            if self.advance() != '_' {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    fn number_error(&mut self, message: &str) -> Option<Scanned> {
+        self.errors.push(LoxError::scan(
+            ErrorCode::L0015,
+            self.current_span(),
+            message,
+        ));
+        None
+    }
+
+    /// Scans the rest of a string after its opening `"`, or after the `}`
+    /// closing an interpolated expression. Stops early at a `${`.
+    fn string(&mut self) -> Option<Scanned> {
+        let mut value = String::new();
+        while self.peek() != '"' && !self.is_at_end() {
+            if self.peek() == '$' && self.peek_next() == '{' {
+                self.advance();
+                self.advance();
+                self.interpolations.push(0);
+                return self.add_token_literal(TokenType::INTERPOLATION, Some(Object::Str(value)));
+            }
+            match self.advance() {
+                '\n' => {
+                    self.newline();
+                    value.push('\n');
+                }
+                '\\' => {
+                    if let Some(c) = self.escape() {
+                        value.push(c);
+                    }
+                }
+                c => value.push(c),
+            }
+        }
+
+        if self.is_at_end() {
+            self.errors.push(LoxError::scan(
+                ErrorCode::L0001,
+                self.current_span(),
+                "Unterminated string.",
+            ));
+            // Everything up to the end was swallowed, enclosing
+            // interpolations included.
+            self.interpolations.clear();
+            return None;
+        }
+
+        self.advance();
+
+        // A bad escape is reported but still yields the string, so the parser
+        // doesn't pile further errors on top.
+        self.add_token_literal(TokenType::STRING, Some(Object::Str(value)))
+    }
+
+    /// Decodes the escape sequence after a `\\` in a string, reporting it
+    /// if it isn't valid.
+    fn escape(&mut self) -> Option<char> {
+        let start = self.current - 1;
+        let column = self.column - 1;
+        if self.is_at_end() {
+            // The string is unterminated, which is reported instead.
+            return None;
+        }
+
+        let c = self.advance();
+        let escaped = match c {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            '\\' => Some('\\'),
+            '"' => Some('"'),
+            '0' => Some('\0'),
+            '$' => Some('$'),
+            'u' => return self.unicode_escape(start, column),
+            _ => None,
+        };
+        if escaped.is_none() {
+            let span = Span::new(start, self.current, self.line, column);
+            // Lox has no line continuations, so a backslash at the end of a
+            // line is unknown like any other.
+            let message = match c {
+                '\n' => "Unknown escape sequence '\\' at end of line.".to_string(),
+                _ => format!("Unknown escape sequence '\\{}'.", c.escape_debug()),
+            };
+            self.errors
+                .push(LoxError::scan(ErrorCode::L0011, span, &message));
+        }
+        if c == '\n' {
+            // After the error, so it points at the line with the backslash.
+            self.newline();
+        }
+        escaped
+    }
+
+    /// Decodes the `{1F600}` part of a `\\u{1F600}` escape.
+    fn unicode_escape(&mut self, start: usize, column: usize) -> Option<char> {
+        let well_formed = self.match_char('{') && {
+            let digits = self.current;
+            while self.peek().is_ascii_hexdigit() {
+                self.advance();
+            }
+            let count = self.current - digits;
+            let closed = self.match_char('}');
+            (1..=6).contains(&count) && closed
+        };
+        let span = Span::new(start, self.current, self.line, column);
+        if !well_formed {
+            self.errors.push(LoxError::scan(
+                ErrorCode::L0012,
+                span,
+                "Invalid Unicode escape; expected '\\u{' followed by 1 to 6 hex digits and '}'.",
+            ));
+            return None;
+        }
+
+        let digits = &self.source[start + 3..self.current - 1];
+        let code_point = u32::from_str_radix(digits, 16).expect("Checked to be 1 to 6 hex digits");
+        let character = char::from_u32(code_point);
+        if character.is_none() {
+            self.errors.push(LoxError::scan(
+                ErrorCode::L0012,
+                span,
+                &format!(
+                    "Invalid Unicode escape; U+{:X} is not a Unicode scalar value.",
+                    code_point
+                ),
+            ));
+        }
+        character
+    }
+
+    fn match_char(&mut self, expected: char) -> bool {
+        if self.is_at_end() || self.peek() != expected {
+            return false;
+        }
+
+        self.advance();
+        true
+    }
+
+    fn peek(&self) -> char {
+        self.source[self.current..].chars().next().unwrap_or('\0')
+    }
+
+    fn peek_next(&self) -> char {
+        self.source[self.current..].chars().nth(1).unwrap_or('\0')
+    }
+
+    fn is_alpha(&self, c: char) -> bool {
+        c.is_alphabetic() || c == '_'
+    }
+
+    fn is_alpha_numeric(&self, c: char) -> bool {
+        self.is_alpha(c) || self.is_digit(c)
+    }
+
+    fn is_digit(&self, c: char) -> bool {
+        c.is_ascii_digit()
+    }
+
+    /// Every reserved word, in the order `keywords` matches them.
+    pub const KEYWORDS: [&'static str; 16] = [
+        "and", "class", "else", "false", "for", "fun", "if", "nil", "or", "print", "return",
+        "super", "this", "true", "var", "while",
+    ];
+
+    fn keywords(check: &str) -> Option<TokenType> {
+        match check {
+            "and" => Some(TokenType::AND),
+            "class" => Some(TokenType::CLASS),
+            "else" => Some(TokenType::ELSE),
+            "false" => Some(TokenType::FALSE),
+            "for" => Some(TokenType::FOR),
+            "fun" => Some(TokenType::FUN),
+            "if" => Some(TokenType::IF),
+            "nil" => Some(TokenType::NIL),
+            "or" => Some(TokenType::OR),
+            "print" => Some(TokenType::PRINT),
+            "return" => Some(TokenType::RETURN),
+            "super" => Some(TokenType::SUPER),
+            "this" => Some(TokenType::THIS),
+            "true" => Some(TokenType::TRUE),
+            "var" => Some(TokenType::VAR),
+            "while" => Some(TokenType::WHILE),
+            _ => None,
+        }
+    }
+}
+
+impl Scanner<'_> {
+    /// The next token, scanning only as much source as it needs. The last
+    /// token is always a single EOF.
+    fn next_scanned(&mut self) -> Option<Scanned> {
+        while !self.is_at_end() {
+            self.start = self.current;
+            self.start_line = self.line;
+            self.start_column = self.column;
+            if let Some(scanned) = self.scan_token() {
+                return Some(scanned);
+            }
+        }
+
+        if self.done {
+            return None;
+        }
+        self.done = true;
+        self.start = self.current;
+        self.start_line = self.line;
+        self.start_column = self.column;
+        if !self.interpolations.is_empty() {
+            self.interpolations.clear();
+            self.errors.push(LoxError::scan(
+                ErrorCode::L0013,
+                self.current_span(),
+                "Unterminated string interpolation.",
+            ));
+        }
+        Some(Scanned {
+            token_type: TokenType::EOF,
+            literal: None,
+            span: self.current_span(),
+        })
+    }
+}
+
+impl Iterator for Scanner<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        let Scanned {
+            token_type,
+            literal,
+            span,
+        } = self.next_scanned()?;
+        let lexeme = self.source[span.start..span.end].to_string();
+        let token = Token::new(token_type, lexeme, literal, span);
+        let token = self.attach_doc(token);
+        Some(self.attach_trivia(token))
+    }
+}
+
+impl FusedIterator for Scanner<'_> {}
+
+impl Scanner<'_> {
+    // How many characters past the end of a token the scanner may look at
+    // to decide where it ends, as in `1.5` against `1.x`.
+    const LOOKAHEAD: usize = 2;
+
+    /// Updates `tokens`, the result of scanning some source, for `edit`,
+    /// given the `source` after the edit. Only the tokens around the edit
+    /// are scanned again; the ones after it are reused with their spans
+    /// shifted. Errors are reported for the rescanned region only, so
+    /// `tokens` should come from a scan without errors, as returned by
+    /// [`Scanner::scan_tokens`]. Tokens scanned with trivia aren't supported.
+    pub fn rescan(
+        mut tokens: Vec<Token>,
+        edit: &TextEdit,
+        source: &str,
+    ) -> Result<Vec<Token>, Vec<LoxError>> {
+        // Restart after the last token that ends far enough before the edit
+        // not to have looked at it, outside any string interpolation.
+        let mut scanner = Scanner::borrowed(source);
+        let mut kept = 0;
+        let mut depth = 0;
+        for (index, token) in tokens.iter().enumerate() {
+            let end = token.span().end;
+            if end >= edit.range.start {
+                break;
+            }
+            depth = Self::interpolation_depth(depth, token);
+            let gap = &source[end..edit.range.start];
+            if depth == 0 && gap.chars().nth(Self::LOOKAHEAD - 1).is_some() {
+                kept = index + 1;
+                (scanner.line, scanner.column) = Self::end_position(token);
+                scanner.current = end;
+            }
+        }
+
+        // Scan until a token ends past the edit at the same place, relative
+        // to the source after it, as an old token, both outside any
+        // interpolation. From there on the old scan carries on unchanged.
+        let edit_end = edit.range.start + edit.text.len();
+        let mut old = kept;
+        let mut old_depth = 0;
+        let mut rescanned = Vec::new();
+        let resync = loop {
+            let Some(token) = scanner.next() else {
+                break None;
+            };
+            if *token.token_type() == TokenType::EOF {
+                rescanned.push(token);
+                break None;
+            }
+            let end = token.span().end;
+            rescanned.push(token);
+            if end < edit_end || !scanner.interpolations.is_empty() {
+                continue;
+            }
+
+            let old_end = end - edit.text.len() + edit.range.len();
+            while old < tokens.len() && tokens[old].span().end < old_end {
+                old_depth = Self::interpolation_depth(old_depth, &tokens[old]);
+                old += 1;
+            }
+            if let Some(token) = tokens.get(old)
+                && token.span().end == old_end
+                && *token.token_type() != TokenType::EOF
+                && Self::interpolation_depth(old_depth, token) == 0
+            {
+                break Some(old);
+            }
+        };
+        if !scanner.errors.is_empty() {
+            return Err(scanner.errors);
+        }
+
+        let Some(old) = resync else {
+            tokens.truncate(kept);
+            tokens.append(&mut rescanned);
+            return Ok(tokens);
+        };
+        let (old_line, old_column) = Self::end_position(&tokens[old]);
+        for token in &mut tokens[old + 1..] {
+            let span = token.span();
+            let column = if span.line == old_line {
+                span.column - old_column + scanner.column
+            } else {
+                span.column
+            };
+            token.set_span(Span::new(
+                span.start - edit.range.len() + edit.text.len(),
+                span.end - edit.range.len() + edit.text.len(),
+                span.line - old_line + scanner.line,
+                column,
+            ));
+        }
+        tokens.splice(kept..=old, rescanned);
+        Ok(tokens)
+    }
+
+    /// How many interpolations are open after `token`, given how many were
+    /// open before it.
+    fn interpolation_depth(depth: usize, token: &Token) -> usize {
+        let opens = token.lexeme().starts_with('"');
+        match token.token_type() {
+            TokenType::INTERPOLATION if opens => depth + 1,
+            TokenType::STRING if !opens => depth - 1,
+            _ => depth,
+        }
+    }
+
+    /// The line and column just past the end of `token`.
+    fn end_position(token: &Token) -> (usize, usize) {
+        let lexeme = token.lexeme();
+        match lexeme.rfind('\n') {
+            Some(index) => (
+                token.line() + lexeme.matches('\n').count(),
+                lexeme[index + 1..].chars().count() + 1,
+            ),
+            None => (token.line(), token.column() + lexeme.chars().count()),
+        }
+    }
+}
+
+/// Scans tokens whose lexemes borrow from the source rather than each
+/// owning a copy, with identifiers interned. Only string literals with their
+/// decoded values and doc comments allocate. Trivia isn't kept; use
+/// [`Scanner::keep_trivia`] for that.
+///
+/// This is for tools that only look at tokens. The parser still takes owned
+/// [`Token`]s, and converting with `BorrowedToken::to_token` allocates the
+/// lexeme again, so the interpreters use [`Scanner`].
+pub struct BorrowedScanner<'src, 'i> {
+    source: &'src str,
+    scanner: Scanner<'src>,
+    interner: &'i mut Interner<'src>,
+}
+
+impl<'src, 'i> BorrowedScanner<'src, 'i> {
+    pub fn new(source: &'src str, interner: &'i mut Interner<'src>) -> Self {
+        Self {
+            source,
+            scanner: Scanner::borrowed(source),
+            interner,
+        }
+    }
+
+    /// Like [`Scanner::scan_tokens`].
+    pub fn scan_tokens(&mut self) -> Result<Vec<BorrowedToken<'src>>, Vec<LoxError>> {
+        let tokens: Vec<BorrowedToken<'src>> = self.by_ref().collect();
+        if self.scanner.errors.is_empty() {
+            Ok(tokens)
+        } else {
+            Err(self.scanner.errors.clone())
+        }
+    }
+
+    pub fn errors(&self) -> &[LoxError] {
+        self.scanner.errors()
+    }
+}
+
+impl<'src> Iterator for BorrowedScanner<'src, '_> {
+    type Item = BorrowedToken<'src>;
+
+    fn next(&mut self) -> Option<BorrowedToken<'src>> {
+        let Scanned {
+            token_type,
+            literal,
+            span,
+        } = self.scanner.next_scanned()?;
+        let lexeme = &self.source[span.start..span.end];
+        let symbol = (token_type == TokenType::IDENTIFIER).then(|| self.interner.intern(lexeme));
+        let doc = self.scanner.doc.take().map(String::into_boxed_str);
+        Some(BorrowedToken {
+            token_type,
+            lexeme,
+            literal,
+            span,
+            symbol,
+            doc,
+        })
+    }
+}
+
+impl FusedIterator for BorrowedScanner<'_, '_> {}
+
+/// Whether every `_` in `literal` has a digit in `radix` on both sides, as
+/// in `1_000`, so `1_`, `1_.5` and `0x_1` are rejected.
+fn separates_digits(literal: &str, radix: u32) -> bool {
+    let chars: Vec<char> = literal.chars().collect();
+    chars.iter().enumerate().all(|(i, c)| {
+        *c != '_'
+            || (i > 0
+                && chars[i - 1].is_digit(radix)
+                && chars.get(i + 1).is_some_and(|next| next.is_digit(radix)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyword_list_matches_keywords() {
+        for keyword in Scanner::KEYWORDS {
+            assert!(Scanner::keywords(keyword).is_some(), "{}", keyword);
+        }
+    }
+
+    #[test]
+    fn initial_lexemes() {
+        let mut scanner = Scanner::new("(){},.-+*;".to_string());
+
+        let tokens = scanner.scan_tokens().unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(
+                    TokenType::LEFT_PAREN,
+                    "(".to_string(),
+                    None,
+                    Span::new(0, 1, 1, 1)
+                ),
+                Token::new(
+                    TokenType::RIGHT_PAREN,
+                    ")".to_string(),
+                    None,
+                    Span::new(1, 2, 1, 2)
+                ),
+                Token::new(
+                    TokenType::LEFT_BRACE,
+                    "{".to_string(),
+                    None,
+                    Span::new(2, 3, 1, 3)
+                ),
+                Token::new(
+                    TokenType::RIGHT_BRACE,
+                    "}".to_string(),
+                    None,
+                    Span::new(3, 4, 1, 4)
+                ),
+                Token::new(
+                    TokenType::COMMA,
+                    ",".to_string(),
+                    None,
+                    Span::new(4, 5, 1, 5)
+                ),
+                Token::new(TokenType::DOT, ".".to_string(), None, Span::new(5, 6, 1, 6)),
+                Token::new(
+                    TokenType::MINUS,
+                    "-".to_string(),
+                    None,
+                    Span::new(6, 7, 1, 7)
+                ),
+                Token::new(
+                    TokenType::PLUS,
+                    "+".to_string(),
+                    None,
+                    Span::new(7, 8, 1, 8)
+                ),
+                Token::new(
+                    TokenType::STAR,
+                    "*".to_string(),
+                    None,
+                    Span::new(8, 9, 1, 9)
+                ),
+                Token::new(
+                    TokenType::SEMICOLON,
+                    ";".to_string(),
+                    None,
+                    Span::new(9, 10, 1, 10)
+                ),
+                Token::new(
+                    TokenType::EOF,
+                    "".to_string(),
+                    None,
+                    Span::new(10, 10, 1, 11)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_operators() {
+        let mut scanner = Scanner::new("! != = == < <= > >=".to_string());
+
+        let tokens = scanner.scan_tokens().unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(
+                    TokenType::BANG,
+                    "!".to_string(),
+                    None,
+                    Span::new(0, 1, 1, 1)
+                ),
+                Token::new(
+                    TokenType::BANG_EQUAL,
+                    "!=".to_string(),
+                    None,
+                    Span::new(2, 4, 1, 3)
+                ),
+                Token::new(
+                    TokenType::EQUAL,
+                    "=".to_string(),
+                    None,
+                    Span::new(5, 6, 1, 6)
+                ),
+                Token::new(
+                    TokenType::EQUAL_EQUAL,
+                    "==".to_string(),
+                    None,
+                    Span::new(7, 9, 1, 8)
+                ),
+                Token::new(
+                    TokenType::LESS,
+                    "<".to_string(),
+                    None,
+                    Span::new(10, 11, 1, 11)
+                ),
+                Token::new(
+                    TokenType::LESS_EQUAL,
+                    "<=".to_string(),
+                    None,
+                    Span::new(12, 14, 1, 13)
+                ),
+                Token::new(
+                    TokenType::GREATER,
+                    ">".to_string(),
+                    None,
+                    Span::new(15, 16, 1, 16)
+                ),
+                Token::new(
+                    TokenType::GREATER_EQUAL,
+                    ">=".to_string(),
+                    None,
+                    Span::new(17, 19, 1, 18)
+                ),
+                Token::new(
+                    TokenType::EOF,
+                    "".to_string(),
+                    None,
+                    Span::new(19, 19, 1, 20)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        let mut scanner = Scanner::new("// stufff herer comments".to_string());
+
+        let tokens = scanner.scan_tokens().unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![Token::new(
+                TokenType::EOF,
+                "".to_string(),
+                None,
+                Span::new(24, 24, 1, 25)
+            ),]
+        );
+    }
+
+    #[test]
+    fn test_block_comments() {
+        let mut scanner =
+            Scanner::new("1 /* a /* nested\n */ still comment */ 2\n/**/3 /*/ x */ 4".to_string());
+
+        let tokens = scanner.scan_tokens().unwrap();
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| (token.lexeme().as_str(), token.line(), token.column()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("1", 1, 1),
+                ("2", 2, 22),
+                ("3", 3, 5),
+                ("4", 3, 16),
+                ("", 3, 17)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unterminated_block_comment() {
+        let mut scanner = Scanner::new("print 1; /* outer /* inner */\n".to_string());
+
+        let errors = scanner.scan_tokens().unwrap_err();
+
+        assert_eq!(
+            errors,
+            vec![LoxError::scan(
+                ErrorCode::L0016,
+                Span::new(9, 30, 1, 10),
+                "Unterminated block comment."
+            )]
+        );
+    }
+
+    #[test]
+    fn test_doc_comments() {
+        let source =
+            "/// Prints one.\n///\n///   Indented.\r\nprint 1;\n// plain\n//// not docs\nprint 2;";
+        let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| (token.lexeme().as_str(), token.doc()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("print", Some("Prints one.\n\n  Indented.")),
+                ("1", None),
+                (";", None),
+                ("print", None),
+                ("2", None),
+                (";", None),
+                ("", None),
+            ]
+        );
+    }
+
+    fn trivia_tokens(source: &str) -> Vec<Token> {
+        let mut scanner = Scanner::new(source.to_string());
+        scanner.keep_trivia(true);
+        scanner.by_ref().collect()
+    }
+
+    fn reassemble(tokens: &[Token]) -> String {
+        tokens
+            .iter()
+            .map(|token| {
+                format!(
+                    "{}{}{}",
+                    token.leading_trivia(),
+                    token.lexeme(),
+                    token.trailing_trivia()
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_trivia() {
+        let source = "  print 1 ;  // one\r\n/* two */\tprint \"a${ 2 }b\"; /// doc\nprint 3;\n";
+        let tokens = trivia_tokens(source);
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| (
+                    token.leading_trivia(),
+                    token.lexeme().as_str(),
+                    token.trailing_trivia()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("  ", "print", " "),
+                ("", "1", " "),
+                ("", ";", "  // one\r"),
+                ("\n/* two */\t", "print", " "),
+                ("", "\"a${", " "),
+                ("", "2", " "),
+                ("", "}b\"", ""),
+                ("", ";", " "),
+                ("/// doc\n", "print", " "),
+                ("", "3", ""),
+                ("", ";", ""),
+                ("\n", "", ""),
+            ]
+        );
+        assert_eq!(tokens[8].doc(), Some("doc"));
+        assert_eq!(reassemble(&tokens), source);
+    }
+
+    #[test]
+    fn test_trivia_reproduces_source() {
+        let sources = [
+            "",
+            "   \n\n",
+            "print 1",
+            "print \"unterminated\n",
+            "print @ # 1;",
+            "/* a /* b */ c */ 1 /* open",
+            "print \"${ \"${ 1 }\" }\" // nested\n",
+            "print \"héllo\"; // ünïcode ✓\n\t0x1F + 1e3 / 0b;",
+        ];
+        for source in sources {
+            assert_eq!(reassemble(&trivia_tokens(source)), source);
+        }
+    }
+
+    #[test]
+    fn test_trivia_is_off_by_default() {
+        let tokens = Scanner::new(" 1 // x\n".to_string()).scan_tokens().unwrap();
+
+        assert!(
+            tokens.iter().all(
+                |token| token.leading_trivia().is_empty() && token.trailing_trivia().is_empty()
+            )
+        );
+    }
+
+    #[test]
+    fn test_borrowed_tokens_match_owned_tokens() {
+        let source = "/// Doc.\nprint \"a${b}\\n\" + 0x1F + foo_bar; // x\nfoo_bar; baz; ünï";
+        let owned = Scanner::new(source.to_string()).scan_tokens().unwrap();
+        let mut interner = Interner::new();
+        let borrowed = BorrowedScanner::new(source, &mut interner)
+            .scan_tokens()
+            .unwrap();
+
+        assert_eq!(
+            borrowed
+                .iter()
+                .map(BorrowedToken::to_token)
+                .collect::<Vec<_>>(),
+            owned
+        );
+        assert!(borrowed.iter().all(|token| std::ptr::eq(
+            token.lexeme(),
+            &source[token.span().start..token.span().end]
+        )));
+    }
+
+    #[test]
+    fn test_borrowed_tokens_intern_identifiers() {
+        let source = "foo + bar * foo or bar";
+        let mut interner = Interner::new();
+        let tokens = BorrowedScanner::new(source, &mut interner)
+            .scan_tokens()
+            .unwrap();
+
+        let symbols: Vec<_> = tokens.iter().map(BorrowedToken::symbol).collect();
+        let foo = symbols[0].unwrap();
+        let bar = symbols[2].unwrap();
+        assert_ne!(foo, bar);
+        assert_eq!(
+            symbols,
+            vec![
+                Some(foo),
+                None,
+                Some(bar),
+                None,
+                Some(foo),
+                None,
+                Some(bar),
+                None
+            ]
+        );
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.resolve(foo), "foo");
+        assert_eq!(interner.resolve(bar), "bar");
+    }
+
+    #[test]
+    fn test_borrowed_scanner_reports_errors() {
+        let mut interner = Interner::new();
+        let mut scanner = BorrowedScanner::new("print @;", &mut interner);
+
+        let errors = scanner.scan_tokens().unwrap_err();
+
+        assert_eq!(errors, scanner.errors());
+        assert_eq!(errors[0].message(), "Unexpected character.");
+    }
+
+    fn check_rescan(source: &str, edit: TextEdit) {
+        let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+        let mut edited = source.to_string();
+        edited.replace_range(edit.range.clone(), edit.text);
+
+        assert_eq!(
+            Scanner::rescan(tokens, &edit, &edited),
+            Scanner::new(edited.clone()).scan_tokens(),
+            "{:?} edited by {:?}",
+            source,
+            edit
+        );
+    }
+
+    #[test]
+    fn test_rescan() {
+        let source = "print 1 + 2;\nprint \"a${1}b\";\n/// Doc.\nprint 3.5;\n";
+        let edits = [
+            // Inside a number, on the first line.
+            (7..7, "0"),
+            // Joining two lines and shifting later lines up.
+            (12..13, " "),
+            // Adding lines.
+            (0..0, "print 0;\n\n"),
+            // Inside an interpolation.
+            (23..24, "1 + 2"),
+            // Turning `3.5` into `3.x` and into `35`.
+            (46..47, "x"),
+            (45..46, ""),
+            // Opening a string that runs to the end.
+            (6..6, "\""),
+            // Commenting out the rest of the file.
+            (13..13, "/*"),
+            // Breaking a doc comment.
+            (31..32, ""),
+            // Deleting everything.
+            (0..source.len(), ""),
+        ];
+        for (range, text) in edits {
+            check_rescan(source, TextEdit { range, text });
+        }
+    }
+
+    #[test]
+    fn test_rescan_reuses_tokens_after_edit() {
+        let source = "print 1;\nprint 2;\nprint 3;";
+        let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+        let edit = TextEdit {
+            range: 6..7,
+            text: "10 +\n 1",
+        };
+
+        let tokens = Scanner::rescan(tokens, &edit, "print 10 +\n 1;\nprint 2;\nprint 3;").unwrap();
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| (token.lexeme().as_str(), token.span()))
+                .collect::<Vec<_>>()[2..6],
+            [
+                ("+", Span::new(9, 10, 1, 10)),
+                ("1", Span::new(12, 13, 2, 2)),
+                (";", Span::new(13, 14, 2, 3)),
+                ("print", Span::new(15, 20, 3, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rescan_matches_full_scan() {
+        let source = "var a = 1_000 + 0x1F; // one\n\
+                      print \"x${a} ${\"${b}\"} ü\" + c.d;\n\
+                      /* a /* b */ */ /// doc\n\
+                      if (a >= 2.5e3) { print !nil; }\n";
+        let pieces = [
+            "", "1", ".", "\"", "${", "}", "/*", "*/", "//", "\n", " ", "é", "x", "=", "{",
+        ];
+        let boundaries: Vec<usize> = source
+            .char_indices()
+            .map(|(index, _)| index)
+            .chain([source.len()])
+            .collect();
+
+        // A fixed linear congruential generator, so failures reproduce.
+        let mut seed: u64 = 0x5eed;
+        let mut random = |bound: usize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as usize % bound
+        };
+        for _ in 0..2000 {
+            let start = boundaries[random(boundaries.len())];
+            let end = boundaries[random(boundaries.len())].max(start);
+            let end = if random(2) == 0 {
+                start
+            } else {
+                end.min(start + 8)
+            };
+            let end = boundaries[boundaries.partition_point(|index| *index < end)];
+            let text = pieces[random(pieces.len())];
+            check_rescan(
+                source,
+                TextEdit {
+                    range: start..end,
+                    text,
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_slash() {
+        let mut scanner = Scanner::new("/".to_string());
+
+        let tokens = scanner.scan_tokens().unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(
+                    TokenType::SLASH,
+                    "/".to_string(),
+                    None,
+                    Span::new(0, 1, 1, 1)
+                ),
+                Token::new(TokenType::EOF, "".to_string(), None, Span::new(1, 1, 1, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_increment() {
+        let mut scanner = Scanner::new("/\n*\n".to_string());
+
+        let tokens = scanner.scan_tokens().unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(
+                    TokenType::SLASH,
+                    "/".to_string(),
+                    None,
+                    Span::new(0, 1, 1, 1)
+                ),
+                Token::new(
+                    TokenType::STAR,
+                    "*".to_string(),
+                    None,
+                    Span::new(2, 3, 2, 1)
+                ),
+                Token::new(TokenType::EOF, "".to_string(), None, Span::new(4, 4, 3, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_string_literal() {
+        let mut scanner = Scanner::new("\"this is a string\"".to_string());
+
+        let tokens = scanner.scan_tokens().unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(
+                    TokenType::STRING,
+                    "\"this is a string\"".to_string(),
+                    Some(Object::Str("this is a string".to_string())),
+                    Span::new(0, 18, 1, 1)
+                ),
+                Token::new(
+                    TokenType::EOF,
+                    "".to_string(),
+                    None,
+                    Span::new(18, 18, 1, 19)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let source = r#""tab\there \"quoted\" \\ \r\n\0 \u{1F600}\u{e9}""#;
+        let mut scanner = Scanner::new(source.to_string());
+
+        let tokens = scanner.scan_tokens().unwrap();
+
+        assert_eq!(tokens[0].lexeme(), source);
+        assert_eq!(
+            tokens[0].literal(),
+            Some(&Object::Str(
+                "tab\there \"quoted\" \\ \r\n\0 😀é".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_invalid_escapes() {
+        let source = "\"a\\qb\" \"\\u{110000}\" \"\\u{D800}\" \"\\u{}\" \"\\u41\" \"\\u{1234567}\"";
+        let mut scanner = Scanner::new(source.to_string());
+
+        let errors = scanner.scan_tokens().unwrap_err();
+
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|error| {
+                let span = error.span();
+                (error.code(), &source[span.start..span.end], span.column)
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (ErrorCode::L0011, "\\q", 3),
+                (ErrorCode::L0012, "\\u{110000}", 9),
+                (ErrorCode::L0012, "\\u{D800}", 22),
+                (ErrorCode::L0012, "\\u{}", 33),
+                (ErrorCode::L0012, "\\u", 40),
+                (ErrorCode::L0012, "\\u{1234567}", 47),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_backslash_at_end_of_line() {
+        let mut scanner = Scanner::new("print \"a\\\nb\";\nprint 1;".to_string());
+
+        let errors = scanner.scan_tokens().unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code(), ErrorCode::L0011);
+        assert_eq!(
+            errors[0].message(),
+            "Unknown escape sequence '\\' at end of line."
+        );
+        assert_eq!(errors[0].line(), 1);
+        assert_eq!(errors[0].span().column, 9);
+        assert_eq!(scanner.line, 3);
+    }
+
+    #[test]
+    fn test_escaped_quote_does_not_end_string() {
+        let mut scanner = Scanner::new("\"a\\\"".to_string());
+
+        let errors = scanner.scan_tokens().unwrap_err();
+
+        assert_eq!(errors[0].message(), "Unterminated string.");
+    }
+
+    #[test]
+    fn test_whole_number() {
+        let mut scanner = Scanner::new("123".to_string());
+
+        let tokens = scanner.scan_tokens().unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(
+                    TokenType::NUMBER,
+                    "123".to_string(),
+                    Some(Object::Num(123_f64)),
+                    Span::new(0, 3, 1, 1)
+                ),
+                Token::new(TokenType::EOF, "".to_string(), None, Span::new(3, 3, 1, 4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decimal_number() {
+        let mut scanner = Scanner::new("123.456".to_string());
+
+        let tokens = scanner.scan_tokens().unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(
+                    TokenType::NUMBER,
+                    "123.456".to_string(),
+                    Some(Object::Num(123.456)),
+                    Span::new(0, 7, 1, 1)
+                ),
+                Token::new(TokenType::EOF, "".to_string(), None, Span::new(7, 7, 1, 8)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut scanner = Scanner::new("@\n\"open".to_string());
+
+        let errors = scanner.scan_tokens().unwrap_err();
+
+        assert_eq!(
+            errors,
+            vec![
+                LoxError::scan(
+                    ErrorCode::L0002,
+                    Span::new(0, 1, 1, 1),
+                    "Unexpected character."
+                ),
+                LoxError::scan(
+                    ErrorCode::L0001,
+                    Span::new(2, 7, 2, 1),
+                    "Unterminated string."
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_number_literals() {
+        let source = "0xFF 0xdead_BEEF 0o17 0b1010_1010 1_000_000 6.02e23 1E-3 2.5e+1_0 007 1.";
+
+        let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+
+        let values: Vec<_> = tokens
+            .iter()
+            .filter_map(|token| match token.literal() {
+                Some(Object::Num(value)) => Some(*value),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                255.0,
+                3735928559.0,
+                15.0,
+                170.0,
+                1_000_000.0,
+                6.02e23,
+                1E-3,
+                2.5e+1_0,
+                7.0,
+                1.0
+            ]
+        );
+        assert_eq!(tokens[tokens.len() - 2].token_type(), &TokenType::DOT);
+    }
+
+    #[test]
+    fn test_large_number_literals_round_like_rust() {
+        let source = "0xFF_FFFF_FFFF_FFFF_FFFF 9007199254740993 0.1e-320";
+
+        let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+
+        assert_eq!(
+            tokens[0].literal(),
+            Some(&Object::Num(0xFF_FFFF_FFFF_FFFF_FFFF_u128 as f64))
+        );
+        assert_eq!(tokens[1].literal(), Some(&Object::Num(9007199254740993.0)));
+        assert_eq!(tokens[2].literal(), Some(&Object::Num(0.1e-320)));
+    }
+
+    #[test]
+    fn test_malformed_number_literals() {
+        let source = "0x 0b102 0o8 1e 2e+ 0xg 1e400 0x1_0000_0000_0000_0000_0000_0000_0000_0000 \
+                      1_ 1_.5 1__0 1e_5 1.5_e3 0x_1 0b1_";
+
+        let errors = Scanner::new(source.to_string()).scan_tokens().unwrap_err();
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| {
+                    let span = error.span();
+                    (&source[span.start..span.end], error.message())
+                })
+                .collect::<Vec<_>>(),
+            vec![
+                ("0x", "Expect hexadecimal digits after '0x'."),
+                ("0b102", "Invalid digit '2' in binary literal."),
+                ("0o8", "Invalid digit '8' in octal literal."),
+                ("1e", "Expect digits in exponent."),
+                ("2e+", "Expect digits in exponent."),
+                ("0xg", "Invalid digit 'g' in hexadecimal literal."),
+                ("1e400", "Number literal is too large."),
+                (
+                    "0x1_0000_0000_0000_0000_0000_0000_0000_0000",
+                    "Number literal is too large."
+                ),
+                ("1_", "Expect digits on both sides of '_'."),
+                ("1_.5", "Expect digits on both sides of '_'."),
+                ("1__0", "Expect digits on both sides of '_'."),
+                ("1e_5", "Expect digits on both sides of '_'."),
+                ("1.5_e3", "Expect digits on both sides of '_'."),
+                ("0x_1", "Expect digits on both sides of '_'."),
+                ("0b1_", "Expect digits on both sides of '_'."),
+            ]
+        );
+        assert!(errors.iter().all(|error| error.code() == ErrorCode::L0015));
+    }
+
+    #[test]
+    fn test_keywords() {
+        let mut scanner = Scanner::new("or and class".to_string());
+
+        let tokens = scanner.scan_tokens().unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(TokenType::OR, "or".to_string(), None, Span::new(0, 2, 1, 1)),
+                Token::new(
+                    TokenType::AND,
+                    "and".to_string(),
+                    None,
+                    Span::new(3, 6, 1, 4)
+                ),
+                Token::new(
+                    TokenType::CLASS,
+                    "class".to_string(),
+                    None,
+                    Span::new(7, 12, 1, 8)
+                ),
+                Token::new(
+                    TokenType::EOF,
+                    "".to_string(),
+                    None,
+                    Span::new(12, 12, 1, 13)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_streams_tokens_on_demand() {
+        let mut scanner = Scanner::new("print 1; @ \"unterminated".to_string());
+
+        assert_eq!(
+            scanner.next().map(|token| token.lexeme().clone()),
+            Some("print".to_string())
+        );
+        assert_eq!(
+            scanner.next().map(|token| token.lexeme().clone()),
+            Some("1".to_string())
+        );
+        // Nothing past the tokens handed out has been scanned yet.
+        assert!(scanner.errors().is_empty());
+
+        let rest: Vec<TokenType> = scanner
+            .by_ref()
+            .map(|token| token.token_type().clone())
+            .collect();
+        assert_eq!(rest, vec![TokenType::SEMICOLON, TokenType::EOF]);
+        assert_eq!(scanner.errors().len(), 2);
+        assert_eq!(scanner.next(), None);
+    }
+
+    #[test]
+    fn test_empty_source_yields_single_eof() {
+        let tokens: Vec<Token> = Scanner::new(String::new()).collect();
+
+        assert_eq!(
+            tokens,
+            vec![Token::new(
+                TokenType::EOF,
+                "".to_string(),
+                None,
+                Span::new(0, 0, 1, 1)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let mut scanner = Scanner::new("\"a ${x} b ${ {} \"c${y}\" } d\\${e}\"".to_string());
+
+        let tokens = scanner.scan_tokens().unwrap();
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| (token.token_type().clone(), token.lexeme().as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (TokenType::INTERPOLATION, "\"a ${"),
+                (TokenType::IDENTIFIER, "x"),
+                (TokenType::INTERPOLATION, "} b ${"),
+                (TokenType::LEFT_BRACE, "{"),
+                (TokenType::RIGHT_BRACE, "}"),
+                (TokenType::INTERPOLATION, "\"c${"),
+                (TokenType::IDENTIFIER, "y"),
+                (TokenType::STRING, "}\""),
+                (TokenType::STRING, "} d\\${e}\""),
+                (TokenType::EOF, ""),
+            ]
+        );
+        assert_eq!(
+            tokens[8].literal(),
+            Some(&Object::Str(" d${e}".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_unterminated_interpolation() {
+        let mut scanner = Scanner::new("\"a ${1 + 2".to_string());
+
+        let errors = scanner.scan_tokens().unwrap_err();
+
+        assert_eq!(
+            errors,
+            vec![LoxError::scan(
+                ErrorCode::L0013,
+                Span::new(10, 10, 1, 11),
+                "Unterminated string interpolation."
+            )]
+        );
+    }
+
+    #[test]
+    fn test_unicode() {
+        let mut scanner = Scanner::new("// ünïcödé\n\"héllo 🌍\" naïve".to_string());
+
+        let tokens = scanner.scan_tokens().unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(
+                    TokenType::STRING,
+                    "\"héllo 🌍\"".to_string(),
+                    Some(Object::Str("héllo 🌍".to_string())),
+                    Span::new(15, 28, 2, 1)
+                ),
+                Token::new(
+                    TokenType::IDENTIFIER,
+                    "naïve".to_string(),
+                    None,
+                    Span::new(29, 35, 2, 11)
+                ),
+                Token::new(
+                    TokenType::EOF,
+                    "".to_string(),
+                    None,
+                    Span::new(35, 35, 2, 16)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unexpected_multibyte_character() {
+        let mut scanner = Scanner::new("é€".to_string());
+
+        let errors = scanner.scan_tokens().unwrap_err();
+
+        assert_eq!(
+            errors,
+            vec![LoxError::scan(
+                ErrorCode::L0002,
+                Span::new(2, 5, 1, 2),
+                "Unexpected character."
+            )]
+        );
+    }
+
+    #[test]
+    fn test_large_input_scans_in_linear_time() {
+        // The old scanner indexed chars from the start of the source for
+        // every character, so four times the input took sixteen times as
+        // long. Compare the best of a few runs at two sizes instead of
+        // timing one huge input, which is slow in debug builds.
+        let line = "print \"héllo wörld\" + 12.5; // ünïcödé comment\n";
+        let scan = |bytes: usize| {
+            let source = line.repeat(bytes / line.len());
+            let lines = source.lines().count();
+            let mut best = std::time::Duration::MAX;
+            for _ in 0..5 {
+                let start = std::time::Instant::now();
+                let tokens = Scanner::new(source.clone()).scan_tokens().unwrap();
+                best = best.min(start.elapsed());
+                assert_eq!(tokens.len(), lines * 5 + 1);
+                assert_eq!(tokens.last().unwrap().line(), lines + 1);
+            }
+            best
+        };
+
+        let small = scan(100 * 1024);
+        let large = scan(400 * 1024);
+        assert!(
+            large < small * 10,
+            "4x the input took {:?} instead of {:?}",
+            large,
+            small
+        );
+    }
+}
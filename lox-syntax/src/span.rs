@@ -0,0 +1,94 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of lox-syntax
+
+/// A range of source text: `start..end` in bytes, plus the 1-based line and
+/// column where it starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Self {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    /// The span from the start of `self` to the end of `other`.
+    pub fn to(&self, other: &Span) -> Span {
+        Span {
+            end: other.end,
+            ..*self
+        }
+    }
+
+    /// Renders the line containing the span with the span underlined, e.g.
+    ///
+    /// ```text
+    ///    2 | print 1 +;
+    ///      |          ^
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let line_start = source[..self.start.min(source.len())]
+            .rfind('\n')
+            .map_or(0, |newline| newline + 1);
+        let line_end = source[line_start..]
+            .find('\n')
+            .map_or(source.len(), |newline| line_start + newline);
+        let text = &source[line_start..line_end];
+
+        let underline_end = self.end.clamp(self.start, line_end);
+        let width = source[self.start.min(line_end)..underline_end]
+            .chars()
+            .count()
+            .max(1);
+        let gutter = " ".repeat(self.line.to_string().len());
+
+        format!(
+            " {} | {}\n {} | {}{}",
+            self.line,
+            text,
+            gutter,
+            " ".repeat(self.column.saturating_sub(1)),
+            "^".repeat(width)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let source = "print 1;\nprint 1 +;\n";
+
+        assert_eq!(
+            Span::new(18, 19, 2, 10).render(source),
+            " 2 | print 1 +;\n   |          ^"
+        );
+        assert_eq!(
+            Span::new(0, 7, 1, 1).render(source),
+            " 1 | print 1;\n   | ^^^^^^^"
+        );
+    }
+
+    #[test]
+    fn test_render_at_end_of_input() {
+        let source = "print";
+
+        assert_eq!(
+            Span::new(5, 5, 1, 6).render(source),
+            " 1 | print\n   |      ^"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of lox-syntax
+
+use crate::interner::Symbol;
+use crate::object::Object;
+use crate::span::Span;
+use crate::token_type::TokenType;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    token_type: TokenType,
+    lexeme: String,
+    literal: Option<Object>,
+    span: Span,
+    // Text of the `///` comments right before this token, if any.
+    doc: Option<Box<str>>,
+    // Whitespace and comments around the lexeme, only kept when the scanner
+    // is asked to. Empty boxes don't allocate.
+    leading_trivia: Box<str>,
+    trailing_trivia: Box<str>,
+}
+
+impl Token {
+    pub fn new(token_type: TokenType, lexeme: String, literal: Option<Object>, span: Span) -> Self {
+        Self {
+            token_type,
+            lexeme,
+            literal,
+            span,
+            doc: None,
+            leading_trivia: Box::default(),
+            trailing_trivia: Box::default(),
+        }
+    }
+
+    pub(crate) fn with_doc(mut self, doc: String) -> Self {
+        self.doc = Some(doc.into_boxed_str());
+        self
+    }
+
+    pub(crate) fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub(crate) fn with_trivia(mut self, leading: &str, trailing: &str) -> Self {
+        self.leading_trivia = leading.into();
+        self.trailing_trivia = trailing.into();
+        self
+    }
+
+    pub fn token_type(&self) -> &TokenType {
+        &self.token_type
+    }
+
+    pub fn lexeme(&self) -> &String {
+        &self.lexeme
+    }
+
+    pub fn literal(&self) -> Option<&Object> {
+        // TODO: handle unwrap.
+        self.literal.as_ref()
+    }
+
+    pub fn line(&self) -> usize {
+        self.span.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.span.column
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The `///` doc comment written before this token, one line per comment
+    /// line with the `///` and a single following space removed. rlox-ast's
+    /// parser attaches it to the statement this token starts.
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    /// Source text between the previous token and this one that isn't
+    /// trailing trivia of the previous token: whitespace, newlines, comments
+    /// and any characters that failed to scan. Empty unless the scanner was
+    /// told to keep trivia.
+    pub fn leading_trivia(&self) -> &str {
+        &self.leading_trivia
+    }
+
+    /// Spaces, tabs and a `//` comment following this token on the same
+    /// line. Empty unless the scanner was told to keep trivia.
+    pub fn trailing_trivia(&self) -> &str {
+        &self.trailing_trivia
+    }
+}
+
+/// A [`Token`] whose lexeme borrows from the source, as produced by
+/// [`BorrowedScanner`](crate::BorrowedScanner).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BorrowedToken<'src> {
+    pub(crate) token_type: TokenType,
+    pub(crate) lexeme: &'src str,
+    pub(crate) literal: Option<Object>,
+    pub(crate) span: Span,
+    pub(crate) symbol: Option<Symbol>,
+    pub(crate) doc: Option<Box<str>>,
+}
+
+impl<'src> BorrowedToken<'src> {
+    pub fn token_type(&self) -> &TokenType {
+        &self.token_type
+    }
+
+    pub fn lexeme(&self) -> &'src str {
+        self.lexeme
+    }
+
+    pub fn literal(&self) -> Option<&Object> {
+        self.literal.as_ref()
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The interned name of an identifier; `None` for every other token.
+    pub fn symbol(&self) -> Option<Symbol> {
+        self.symbol
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    /// An owned copy of the token, for handing to the parser.
+    pub fn to_token(&self) -> Token {
+        let token = Token::new(
+            self.token_type.clone(),
+            self.lexeme.to_string(),
+            self.literal.clone(),
+            self.span,
+        );
+        match &self.doc {
+            Some(doc) => token.with_doc(doc.to_string()),
+            None => token,
+        }
+    }
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.token_type,
+            self.lexeme,
+            self.literal.as_ref().unwrap()
+        )
+    }
+}
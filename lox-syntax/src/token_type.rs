@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2024 John Irle
 // SPDX-License-Identifier: MIT
 //
-// This file is part of rlox-ast
+// This file is part of lox-syntax
 
 #[allow(non_camel_case_types)]
 #[derive(Debug, Clone, PartialEq)]
@@ -30,6 +30,8 @@ pub enum TokenType {
     // Literals
     IDENTIFIER,
     STRING,
+    // The part of a string before a `${`, e.g. `"a ${` or `} b ${`.
+    INTERPOLATION,
     NUMBER,
     // Keywords
     AND,
@@ -75,6 +77,7 @@ impl std::fmt::Display for TokenType {
             Self::LESS_EQUAL => write!(f, "LESS_EQUAL"),
             Self::IDENTIFIER => write!(f, "IDENTIFIER"),
             Self::STRING => write!(f, "STRING"),
+            Self::INTERPOLATION => write!(f, "INTERPOLATION"),
             Self::NUMBER => write!(f, "NUMBER"),
             Self::AND => write!(f, "AND"),
             Self::CLASS => write!(f, "CLASS"),
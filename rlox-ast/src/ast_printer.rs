@@ -3,6 +3,8 @@
 //
 // This file is part of rlox-ast
 
+use crate::Object;
+use crate::Span;
 use crate::Token;
 use crate::expr::{Accept, Expr, Visitor};
 
@@ -26,19 +28,26 @@ impl AstPrinter {
     }
 }
 
+impl Default for AstPrinter {
+    fn default() -> Self {
+        AstPrinter::new()
+    }
+}
+
 impl Visitor<String> for AstPrinter {
     fn visit_binary_expr(&self, left: &Expr, operator: &Token, right: &Expr) -> String {
         self.parenthesize(operator.lexeme(), vec![left, right])
     }
 
-    fn visit_grouping_expr(&self, expression: &Expr) -> String {
+    fn visit_grouping_expr(&self, expression: &Expr, _span: &Span) -> String {
         self.parenthesize("group", vec![expression])
     }
 
-    fn visit_literal_expr(&self, value: &str) -> String {
-        if value.is_empty() {
-            return "nil".to_string();
-        }
+    fn visit_interpolation_expr(&self, parts: &[Expr], _span: &Span) -> String {
+        self.parenthesize("interpolate", parts.iter().collect())
+    }
+
+    fn visit_literal_expr(&self, value: &Object, _span: &Span) -> String {
         value.to_string()
     }
 
@@ -57,11 +66,14 @@ mod tests {
         let ast_printer = AstPrinter::new();
         let expr = Expr::Binary(
             Box::new(Expr::Unary(
-                Token::new(TokenType::MINUS, "-".to_string(), None, 1),
-                Box::new(Expr::Literal("123".to_string())),
+                Token::new(TokenType::MINUS, "-".to_string(), None, Span::default()),
+                Box::new(Expr::Literal(Object::Num(123.0), Span::default())),
+            )),
+            Token::new(TokenType::STAR, "*".to_string(), None, Span::default()),
+            Box::new(Expr::Grouping(
+                Box::new(Expr::Literal(Object::Num(45.67), Span::default())),
+                Span::default(),
             )),
-            Token::new(TokenType::STAR, "*".to_string(), None, 1),
-            Box::new(Expr::Grouping(Box::new(Expr::Literal("45.67".to_string())))),
         );
 
         assert_eq!(ast_printer.print(&expr), "(* (- 123) (group 45.67))");
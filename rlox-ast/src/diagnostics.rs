@@ -0,0 +1,114 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of rlox-ast
+
+use crate::{ErrorKind, LoxError};
+
+/// How diagnostics are written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+    /// The message plus the offending source line, for people.
+    #[default]
+    Human,
+    /// One JSON object per line, for tools.
+    Json,
+}
+
+/// Collects the errors reported while running a piece of source code, so the
+/// caller can decide how to show them and how to exit.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    errors: Vec<LoxError>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn report(&mut self, error: LoxError) {
+        self.errors.push(error);
+    }
+
+    /// Whether an I/O, scan, parse or resolve error was reported.
+    pub fn had_error(&self) -> bool {
+        self.errors
+            .iter()
+            .any(|error| !matches!(error.kind(), ErrorKind::Runtime | ErrorKind::Warning))
+    }
+
+    pub fn had_warning(&self) -> bool {
+        self.errors
+            .iter()
+            .any(|error| error.kind() == ErrorKind::Warning)
+    }
+
+    pub fn had_runtime_error(&self) -> bool {
+        self.errors
+            .iter()
+            .any(|error| error.kind() == ErrorKind::Runtime)
+    }
+
+    pub fn errors(&self) -> &[LoxError] {
+        &self.errors
+    }
+
+    pub fn clear(&mut self) {
+        self.errors.clear();
+    }
+
+    /// Writes every reported error to stderr in `format`. `file` is the path
+    /// the source was read from, if it came from a file.
+    pub fn emit(&self, source: &str, file: Option<&str>, format: ErrorFormat) {
+        for error in &self.errors {
+            match format {
+                ErrorFormat::Human => eprintln!("{}", error.render(source, "rlox-ast")),
+                ErrorFormat::Json => eprintln!("{}", error.to_json(file)),
+            }
+        }
+    }
+}
+
+impl Extend<LoxError> for Diagnostics {
+    fn extend<I: IntoIterator<Item = LoxError>>(&mut self, errors: I) {
+        self.errors.extend(errors);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ErrorCode, Span, Token, TokenType};
+
+    #[test]
+    fn test_error_kinds() {
+        let token = Token::new(TokenType::MINUS, "-".to_string(), None, Span::default());
+        let mut diagnostics = Diagnostics::new();
+        assert!(!diagnostics.had_error() && !diagnostics.had_runtime_error());
+
+        diagnostics.report(LoxError::warning(
+            ErrorCode::L0021,
+            Span::default(),
+            "Expression result is unused.",
+        ));
+        assert!(!diagnostics.had_error() && diagnostics.had_warning());
+
+        diagnostics.report(LoxError::runtime(
+            ErrorCode::L0006,
+            &token,
+            "Operand must be a number.",
+        ));
+        assert!(!diagnostics.had_error() && diagnostics.had_runtime_error());
+
+        diagnostics.report(LoxError::parse(
+            ErrorCode::L0003,
+            &token,
+            "Expect expression.",
+        ));
+        assert!(diagnostics.had_error());
+
+        diagnostics.clear();
+        assert!(diagnostics.errors().is_empty());
+    }
+}
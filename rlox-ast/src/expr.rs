@@ -3,19 +3,23 @@
 //
 // This file is part of rlox-ast
 
+use crate::Object;
+use crate::Span;
 use crate::Token;
 
 pub enum Expr {
     Binary(Box<Expr>, Token, Box<Expr>),
-    Grouping(Box<Expr>),
-    Literal(String),
+    Grouping(Box<Expr>, Span),
+    Interpolation(Vec<Expr>, Span),
+    Literal(Object, Span),
     Unary(Token, Box<Expr>),
 }
 
 pub trait Visitor<T> {
     fn visit_binary_expr(&self, left: &Expr, operator: &Token, right: &Expr) -> T;
-    fn visit_grouping_expr(&self, expression: &Expr) -> T;
-    fn visit_literal_expr(&self, value: &str) -> T;
+    fn visit_grouping_expr(&self, expression: &Expr, span: &Span) -> T;
+    fn visit_interpolation_expr(&self, parts: &[Expr], span: &Span) -> T;
+    fn visit_literal_expr(&self, value: &Object, span: &Span) -> T;
     fn visit_unary_expr(&self, operator: &Token, right: &Expr) -> T;
 }
 
@@ -23,12 +27,13 @@ pub trait Accept<T> {
     fn accept<V: Visitor<T>>(&self, visitor: &V) -> T;
 }
 
-impl Accept<String> for Expr {
-    fn accept<V: Visitor<String>>(&self, visitor: &V) -> String {
+impl<T> Accept<T> for Expr {
+    fn accept<V: Visitor<T>>(&self, visitor: &V) -> T {
         match self {
             Self::Binary(left, operator, right) => visitor.visit_binary_expr(left, operator, right),
-            Self::Grouping(expression) => visitor.visit_grouping_expr(expression),
-            Self::Literal(value) => visitor.visit_literal_expr(value),
+            Self::Grouping(expression, span) => visitor.visit_grouping_expr(expression, span),
+            Self::Interpolation(parts, span) => visitor.visit_interpolation_expr(parts, span),
+            Self::Literal(value, span) => visitor.visit_literal_expr(value, span),
             Self::Unary(operator, right) => visitor.visit_unary_expr(operator, right),
         }
     }
@@ -19,10 +19,19 @@ fn main() -> std::io::Result<()> {
         "Expr",
         vec![
             "Binary   ; left: Expr, operator: Token, right: Expr",
-            "Grouping ; expression: Expr",
-            "Literal  ; value: String",
+            "Grouping ; expression: Expr, span: Span",
+            "Interpolation ; parts: Vec<Expr>, span: Span",
+            "Literal  ; value: Object, span: Span",
             "Unary    ; operator: Token, right: Expr",
         ],
+    )?;
+    define_ast(
+        output_dir,
+        "Stmt",
+        vec![
+            "Expression ; expression: Expr, doc: Option<String>, span: Span",
+            "Print      ; expression: Expr, doc: Option<String>, span: Span",
+        ],
     )
 }
 
@@ -37,14 +46,14 @@ fn define_ast(output_dir: &str, base_name: &str, types: Vec<&str>) -> std::io::R
         "// SPDX-FileCopyrightText: 2024 John Irle\n// SPDX-License-Identifier: MIT\n//\n// This file is part of rlox-ast\n"
     )?;
 
-    writeln!(file, "use crate::Token;")?;
+    define_imports(&mut file, base_name, &types)?;
     writeln!(file)?;
     writeln!(file, "pub enum {} {{", base_name)?;
 
     for type_ in &types {
         let (class_name, fields) = parse_type(type_);
 
-        define_type(&mut file, class_name, &fields)?;
+        define_type(&mut file, base_name, class_name, &fields)?;
     }
 
     writeln!(file, "}}")?;
@@ -54,14 +63,44 @@ fn define_ast(output_dir: &str, base_name: &str, types: Vec<&str>) -> std::io::R
     Ok(())
 }
 
+fn define_imports(
+    file: &mut std::fs::File,
+    base_name: &str,
+    types: &[&str],
+) -> std::io::Result<()> {
+    let used = |name: &str| {
+        types
+            .iter()
+            .any(|type_| parse_type(type_).1.iter().any(|(_, typ)| *typ == name))
+    };
+
+    if base_name != "Expr" && used("Expr") {
+        writeln!(file, "use crate::Expr;")?;
+    }
+    if used("Token") {
+        writeln!(file, "use crate::Token;")?;
+    }
+    if used("Object") {
+        writeln!(file, "use crate::Object;")?;
+    }
+    if used("Span") {
+        writeln!(file, "use crate::Span;")?;
+    }
+
+    Ok(())
+}
+
 fn define_type(
     file: &mut std::fs::File,
+    base_name: &str,
     class_name: &str,
     fields: &[(&str, &str)],
 ) -> std::io::Result<()> {
+    // Only self-referential fields need a box to give the enum a known size.
+    let boxed = format!("Box<{}>", base_name);
     let field_types: Vec<&str> = fields
         .iter()
-        .map(|(_, typ)| if *typ == "Expr" { "Box<Expr>" } else { *typ })
+        .map(|(_, typ)| if *typ == base_name { &boxed } else { *typ })
         .collect();
 
     writeln!(file, "    {}({}),", class_name, field_types.join(", "))?;
@@ -84,6 +123,10 @@ fn define_visitor(
             .map(|(name, typ)| {
                 if *typ == "String" {
                     format!("{}: &str", name)
+                } else if let Some(element) =
+                    typ.strip_prefix("Vec<").and_then(|t| t.strip_suffix('>'))
+                {
+                    format!("{}: &[{}]", name, element)
                 } else {
                     format!("{}: &{}", name, typ)
                 }
@@ -109,10 +152,10 @@ fn define_visitor(
     )?;
     writeln!(file, "}}\n")?;
 
-    writeln!(file, "impl Accept<String> for {} {{", base_name)?;
+    writeln!(file, "impl<T> Accept<T> for {} {{", base_name)?;
     writeln!(
         file,
-        "    fn accept<V: Visitor<String>>(&self, visitor: &V) -> String {{"
+        "    fn accept<V: Visitor<T>>(&self, visitor: &V) -> T {{"
     )?;
     writeln!(file, "        match self {{")?;
 
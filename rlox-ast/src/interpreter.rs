@@ -0,0 +1,360 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of rlox-ast
+
+use std::cell::Cell;
+
+use crate::ErrorCode;
+use crate::Object;
+use crate::Span;
+use crate::expr::{self, Expr};
+use crate::stmt::{self, Stmt};
+use crate::{LoxError, StackFrame};
+use crate::{Token, TokenType};
+
+pub struct Interpreter {
+    // How many groupings, unary operators and interpolations are being
+    // evaluated inside one another. Visitors only get `&self`, hence the `Cell`.
+    depth: Cell<usize>,
+    max_depth: usize,
+}
+
+impl Interpreter {
+    pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+    pub fn new() -> Self {
+        Self {
+            depth: Cell::new(0),
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+        }
+    }
+
+    /// Limits how deeply groupings, unary operators and interpolations may nest
+    /// before evaluation fails with a "Stack overflow." runtime error instead
+    /// of exhausting the native stack. Chains like `1 + 2 + 3` don't count,
+    /// as in [`Parser::max_depth`](crate::Parser::max_depth).
+    pub fn max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    pub fn interpret(&self, statements: &[Stmt]) -> Result<(), LoxError> {
+        for statement in statements {
+            self.execute(statement).map_err(|error| {
+                // There are no function calls yet, so top-level code is the
+                // only frame that can be active.
+                let frame = StackFrame {
+                    function: None,
+                    line: error.line(),
+                };
+                error.with_trace(vec![frame])
+            })?;
+        }
+        Ok(())
+    }
+
+    fn execute(&self, statement: &Stmt) -> Result<(), LoxError> {
+        stmt::Accept::accept(statement, self)
+    }
+
+    fn evaluate(&self, expr: &Expr) -> Result<Object, LoxError> {
+        expr::Accept::accept(expr, self)
+    }
+
+    /// Runs `evaluate` one nesting level deeper, failing with a stack
+    /// overflow at `span` instead if that would pass the limit. `depth` is
+    /// restored whether or not it succeeds.
+    fn nested(
+        &self,
+        span: &Span,
+        evaluate: impl FnOnce() -> Result<Object, LoxError>,
+    ) -> Result<Object, LoxError> {
+        let depth = self.depth.get();
+        if depth >= self.max_depth {
+            return Err(LoxError::runtime_at(
+                ErrorCode::L0010,
+                *span,
+                "Stack overflow.",
+            ));
+        }
+        self.depth.set(depth + 1);
+        let result = evaluate();
+        self.depth.set(depth);
+        result
+    }
+
+    fn is_truthy(object: &Object) -> bool {
+        match object {
+            Object::Nil => false,
+            Object::Bool(value) => *value,
+            _ => true,
+        }
+    }
+
+    fn check_number_operand(operator: &Token, operand: &Object) -> Result<f64, LoxError> {
+        match operand {
+            Object::Num(value) => Ok(*value),
+            _ => Err(LoxError::runtime(
+                ErrorCode::L0006,
+                operator,
+                "Operand must be a number.",
+            )),
+        }
+    }
+
+    fn check_number_operands(
+        operator: &Token,
+        left: &Object,
+        right: &Object,
+    ) -> Result<(f64, f64), LoxError> {
+        match (left, right) {
+            (Object::Num(left), Object::Num(right)) => Ok((*left, *right)),
+            _ => Err(LoxError::runtime(
+                ErrorCode::L0007,
+                operator,
+                "Operands must be numbers.",
+            )),
+        }
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
+}
+
+impl stmt::Visitor<Result<(), LoxError>> for Interpreter {
+    fn visit_expression_stmt(
+        &self,
+        expression: &Expr,
+        _doc: &Option<String>,
+        _span: &Span,
+    ) -> Result<(), LoxError> {
+        self.evaluate(expression)?;
+        Ok(())
+    }
+
+    fn visit_print_stmt(
+        &self,
+        expression: &Expr,
+        _doc: &Option<String>,
+        _span: &Span,
+    ) -> Result<(), LoxError> {
+        let value = self.evaluate(expression)?;
+        println!("{}", value);
+        Ok(())
+    }
+}
+
+impl expr::Visitor<Result<Object, LoxError>> for Interpreter {
+    fn visit_binary_expr(
+        &self,
+        left: &Expr,
+        operator: &Token,
+        right: &Expr,
+    ) -> Result<Object, LoxError> {
+        // The parser builds chains like `1 + 2 + 3` as left-nested binary
+        // expressions with no depth limit, so walk down the left operands in
+        // a loop instead of recursing once per operator.
+        let mut operations = vec![(operator, right)];
+        let mut first = left;
+        while let Expr::Binary(left, operator, right) = first {
+            operations.push((operator, right));
+            first = left;
+        }
+
+        let mut value = self.evaluate(first)?;
+        for (operator, right) in operations.into_iter().rev() {
+            let right = self.evaluate(right)?;
+            value = Self::binary(operator, value, right)?;
+        }
+        Ok(value)
+    }
+
+    fn visit_grouping_expr(&self, expression: &Expr, span: &Span) -> Result<Object, LoxError> {
+        self.nested(span, || self.evaluate(expression))
+    }
+
+    fn visit_interpolation_expr(&self, parts: &[Expr], _span: &Span) -> Result<Object, LoxError> {
+        let mut string = String::new();
+        for part in parts {
+            let value = self.nested(&part.span(), || self.evaluate(part))?;
+            string.push_str(&value.to_string());
+        }
+        Ok(Object::Str(string))
+    }
+
+    fn visit_literal_expr(&self, value: &Object, _span: &Span) -> Result<Object, LoxError> {
+        Ok(value.clone())
+    }
+
+    fn visit_unary_expr(&self, operator: &Token, right: &Expr) -> Result<Object, LoxError> {
+        let right = self.nested(&operator.span(), || self.evaluate(right))?;
+
+        match operator.token_type() {
+            TokenType::MINUS => Ok(Object::Num(-Self::check_number_operand(operator, &right)?)),
+            TokenType::BANG => Ok(Object::Bool(!Self::is_truthy(&right))),
+            _ => unreachable!("the parser only builds unary expressions from '!' and '-'"),
+        }
+    }
+}
+
+impl Interpreter {
+    fn binary(operator: &Token, left: Object, right: Object) -> Result<Object, LoxError> {
+        match operator.token_type() {
+            TokenType::MINUS => {
+                let (left, right) = Self::check_number_operands(operator, &left, &right)?;
+                Ok(Object::Num(left - right))
+            }
+            TokenType::SLASH => {
+                let (left, right) = Self::check_number_operands(operator, &left, &right)?;
+                Ok(Object::Num(left / right))
+            }
+            TokenType::STAR => {
+                let (left, right) = Self::check_number_operands(operator, &left, &right)?;
+                Ok(Object::Num(left * right))
+            }
+            TokenType::PLUS => match (left, right) {
+                (Object::Num(left), Object::Num(right)) => Ok(Object::Num(left + right)),
+                (Object::Str(left), Object::Str(right)) => Ok(Object::Str(left + &right)),
+                _ => Err(LoxError::runtime(
+                    ErrorCode::L0008,
+                    operator,
+                    "Operands must be two numbers or two strings.",
+                )),
+            },
+            TokenType::GREATER => {
+                let (left, right) = Self::check_number_operands(operator, &left, &right)?;
+                Ok(Object::Bool(left > right))
+            }
+            TokenType::GREATER_EQUAL => {
+                let (left, right) = Self::check_number_operands(operator, &left, &right)?;
+                Ok(Object::Bool(left >= right))
+            }
+            TokenType::LESS => {
+                let (left, right) = Self::check_number_operands(operator, &left, &right)?;
+                Ok(Object::Bool(left < right))
+            }
+            TokenType::LESS_EQUAL => {
+                let (left, right) = Self::check_number_operands(operator, &left, &right)?;
+                Ok(Object::Bool(left <= right))
+            }
+            TokenType::BANG_EQUAL => Ok(Object::Bool(left != right)),
+            TokenType::EQUAL_EQUAL => Ok(Object::Bool(left == right)),
+            _ => unreachable!("the parser only builds binary expressions from binary operators"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Parser, Scanner};
+
+    fn evaluate(source: &str) -> Result<Object, LoxError> {
+        let tokens = Scanner::new(format!("{};", source)).scan_tokens().unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+        match statements.as_slice() {
+            [Stmt::Expression(expr, _, _)] => Interpreter::new().evaluate(expr),
+            _ => panic!("Expected a single expression statement"),
+        }
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(evaluate("(1 + 2) * 3 - 4 / 2"), Ok(Object::Num(7.0)));
+        assert_eq!(evaluate("-(1 + 2)"), Ok(Object::Num(-3.0)));
+    }
+
+    #[test]
+    fn test_comparison_and_equality() {
+        assert_eq!(evaluate("1 < 2 == !false"), Ok(Object::Bool(true)));
+        assert_eq!(evaluate("nil == false"), Ok(Object::Bool(false)));
+        assert_eq!(evaluate("\"a\" != \"a\""), Ok(Object::Bool(false)));
+    }
+
+    #[test]
+    fn test_string_concatenation() {
+        assert_eq!(
+            evaluate("\"foo\" + \"bar\""),
+            Ok(Object::Str("foobar".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_interpolation() {
+        assert_eq!(
+            evaluate("\"Hello, ${\"Lox\"}! You have ${1 + 2} ${nil} ${1 < 2}\""),
+            Ok(Object::Str("Hello, Lox! You have 3 nil true".to_string()))
+        );
+        assert_eq!(
+            evaluate("\"a${\"b${1.5 * 2}c\"}d\""),
+            Ok(Object::Str("ab3cd".to_string()))
+        );
+
+        let error = evaluate("\"x ${-nil}\"").unwrap_err();
+        assert_eq!(error.message(), "Operand must be a number.");
+    }
+
+    #[test]
+    fn test_runtime_errors() {
+        let error = evaluate("1 + \"a\"").unwrap_err();
+        assert_eq!(
+            error.message(),
+            "Operands must be two numbers or two strings."
+        );
+        assert_eq!(error.lexeme(), Some("+"));
+
+        let error = evaluate("-\"a\"").unwrap_err();
+        assert_eq!(error.message(), "Operand must be a number.");
+    }
+
+    #[test]
+    fn test_stack_overflow() {
+        // Test threads have small stacks, so stay well below the default.
+        let source = format!("print -{}1{};", "(".repeat(40), ")".repeat(40));
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let mut parser = Parser::new(tokens);
+        parser.max_depth(usize::MAX);
+        let statements = parser.parse().unwrap();
+
+        let mut interpreter = Interpreter::new();
+        interpreter.max_depth(20);
+        let error = interpreter.interpret(&statements).unwrap_err();
+        assert_eq!(error.message(), "Stack overflow.");
+        assert_eq!(error.code(), ErrorCode::L0010);
+
+        interpreter.max_depth(41);
+        assert_eq!(interpreter.interpret(&statements), Ok(()));
+    }
+
+    #[test]
+    fn test_flat_chain_is_not_nesting() {
+        let source = format!("1{}", " + 1".repeat(1_000));
+        assert_eq!(evaluate(&source), Ok(Object::Num(1_001.0)));
+        assert_eq!(evaluate("10 - 4 - 3 * 2 / 4"), Ok(Object::Num(4.5)));
+    }
+
+    #[test]
+    fn test_runtime_error_trace() {
+        let tokens = Scanner::new("print 1;\nprint -nil;".to_string())
+            .scan_tokens()
+            .unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+
+        let error = Interpreter::new().interpret(&statements).unwrap_err();
+
+        assert_eq!(
+            error.trace(),
+            [StackFrame {
+                function: None,
+                line: 2
+            }]
+        );
+        assert_eq!(
+            error.to_string(),
+            "Operand must be a number.\n[line 2] in script"
+        );
+    }
+}
@@ -4,33 +4,26 @@
 // This file is part of rlox-ast
 
 mod ast_printer;
+mod diagnostics;
 mod expr;
-mod object;
+mod interpreter;
+mod linter;
 mod parser;
 mod runner;
-mod scanner;
-mod token;
-mod token_type;
+mod span;
+mod stmt;
+mod suggest;
 
+pub use lox_syntax::{
+    BorrowedScanner, BorrowedToken, ErrorCode, ErrorKind, Interner, LoxError, Object, Scanner,
+    Span, StackFrame, Symbol, Token, TokenType,
+};
+
+pub use ast_printer::AstPrinter;
+pub use diagnostics::{Diagnostics, ErrorFormat};
 pub use expr::Expr;
+pub use interpreter::Interpreter;
+pub use linter::Linter;
 pub use parser::Parser;
 pub use runner::Runner;
-pub use scanner::Scanner;
-pub use token::Token;
-pub use token_type::TokenType;
-
-pub fn line_error(line: usize, message: &str) {
-    report(line, "", message);
-}
-
-fn report(line: usize, location: &str, message: &str) {
-    println!("[line {}] Error {}: {}", line, location, message);
-}
-
-fn error(token: &Token, message: &str) {
-    if *token.token_type() == TokenType::EOF {
-        report(token.line(), " at end", message);
-    } else {
-        report(token.line(), &format!(" at '{}'", token.lexeme()), message);
-    }
-}
+pub use stmt::Stmt;
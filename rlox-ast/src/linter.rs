@@ -0,0 +1,92 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of rlox-ast
+
+use crate::expr::Expr;
+use crate::stmt::{Accept, Stmt, Visitor};
+use crate::{ErrorCode, LoxError, Span};
+
+/// Walks a parsed program looking for code that is valid but almost
+/// certainly not what was meant, and reports it as warnings.
+pub struct Linter;
+
+impl Linter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn check(&self, statements: &[Stmt]) -> Vec<LoxError> {
+        statements
+            .iter()
+            .filter_map(|statement| statement.accept(self))
+            .collect()
+    }
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Linter::new()
+    }
+}
+
+impl Visitor<Option<LoxError>> for Linter {
+    fn visit_expression_stmt(
+        &self,
+        expression: &Expr,
+        _doc: &Option<String>,
+        _span: &Span,
+    ) -> Option<LoxError> {
+        // Without assignment or calls, no expression has a side effect, so
+        // a value nobody looks at means the statement does nothing.
+        Some(
+            LoxError::warning(
+                ErrorCode::L0021,
+                expression.span(),
+                "Expression result is unused.",
+            )
+            .with_help("use 'print' to show the value".to_string()),
+        )
+    }
+
+    fn visit_print_stmt(
+        &self,
+        _expression: &Expr,
+        _doc: &Option<String>,
+        _span: &Span,
+    ) -> Option<LoxError> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Parser, Scanner};
+
+    fn check(source: &str) -> Vec<String> {
+        let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+        Linter::new()
+            .check(&statements)
+            .iter()
+            .map(LoxError::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_unused_expression_result() {
+        assert_eq!(
+            check("print 1;\n1 + 2;\nprint \"a\";\n\"b\";"),
+            vec![
+                "[line 2] Warning: Expression result is unused.",
+                "[line 4] Warning: Expression result is unused.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_printed_values_are_used() {
+        assert!(check("print 1 + 2;\nprint -(3);").is_empty());
+    }
+}
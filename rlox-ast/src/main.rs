@@ -3,16 +3,56 @@
 //
 // This file is part of rlox-ast
 
+use rlox::{ErrorCode, ErrorFormat};
+
+const USAGE: &str =
+    "Usage: rlox [--deny-warnings] [--error-format=human|json] [--max-depth=<n>] [script]
+       rlox --explain <code>";
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let [flag, code] = args.as_slice()
+        && flag == "--explain"
+    {
+        explain(code);
+    }
+
     let mut runner = rlox::Runner::new();
-    let args: Vec<String> = std::env::args().collect();
-    match args.len() {
-        1 => runner.run_prompt(),
-        2 => runner.run_file(&args[1]),
-        _ => {
-            println!("Usage: rlox [script]");
+    let mut scripts = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--deny-warnings" => runner.deny_warnings(true),
+            "--error-format=human" => runner.error_format(ErrorFormat::Human),
+            "--error-format=json" => runner.error_format(ErrorFormat::Json),
+            _ if arg.starts_with("--max-depth=") => match arg["--max-depth=".len()..].parse() {
+                Ok(max_depth) => runner.max_depth(max_depth),
+                Err(_) => usage(),
+            },
+            _ if arg.starts_with("--") => usage(),
+            _ => scripts.push(arg),
+        }
+    }
+    match scripts.as_slice() {
+        [] => runner.run_prompt(),
+        [script] => runner.run_file(script),
+        _ => usage(),
+    }
+}
+
+fn explain(name: &str) -> ! {
+    match ErrorCode::from_name(name) {
+        Some(code) => {
+            print!("{}", code.explain());
+            std::process::exit(0);
+        }
+        None => {
+            eprintln!("\"{}\" is not a known error code.", name);
             std::process::exit(64);
         }
     }
-    println!("Hello, world!");
+}
+
+fn usage() -> ! {
+    println!("{}", USAGE);
+    std::process::exit(64);
 }
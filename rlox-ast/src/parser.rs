@@ -4,131 +4,259 @@
 // This file is part of rlox-ast
 
 use TokenType::{
-    BANG_EQUAL, EQUAL_EQUAL, FALSE, GREATER, GREATER_EQUAL, LEFT_PAREN, LESS, LESS_EQUAL, MINUS,
-    NIL, NUMBER, PLUS, RIGHT_PAREN, STRING, TRUE,
+    BANG_EQUAL, EQUAL_EQUAL, FALSE, GREATER, GREATER_EQUAL, INTERPOLATION, LEFT_PAREN, LESS,
+    LESS_EQUAL, MINUS, NIL, NUMBER, PLUS, PRINT, RIGHT_PAREN, SEMICOLON, STRING, TRUE,
 };
 
+use crate::ErrorCode;
+use crate::LoxError;
+use crate::Object;
+use crate::Scanner;
 use crate::TokenType::{BANG, SLASH, STAR};
-use crate::{Expr, Token, TokenType, error};
+use crate::stmt::Stmt;
+use crate::suggest;
+use crate::{Expr, Token, TokenType};
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    // Index of the first token of the statement being parsed.
+    statement_start: usize,
+    // How deeply the expression being parsed is nested, and how deep it may
+    // go before parsing it would risk overflowing the native stack.
+    depth: usize,
+    max_depth: usize,
 }
 
 impl Parser {
+    pub const DEFAULT_MAX_DEPTH: usize = 256;
+
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        Self {
+            tokens,
+            current: 0,
+            statement_start: 0,
+            depth: 0,
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+        }
+    }
+
+    /// Limits how deeply expressions may nest. Parentheses, unary operators
+    /// and interpolated expressions each count as one level; a chain like
+    /// `1 + 1 + 1` is parsed in a loop, so its operators don't.
+    pub fn max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// Parses the whole program, failing with every syntax error in source
+    /// order if there were any.
+    pub fn parse(&mut self) -> Result<Vec<Stmt>, Vec<LoxError>> {
+        let (statements, errors) = self.parse_partial();
+        if errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Parses the whole program, skipping to the next statement after each
+    /// syntax error. Returns the statements that did parse alongside the
+    /// errors so tools can work with partially broken files.
+    pub fn parse_partial(&mut self) -> (Vec<Stmt>, Vec<LoxError>) {
+        let mut statements = Vec::new();
+        let mut errors = Vec::new();
+
+        while !self.is_at_end() {
+            self.statement_start = self.current;
+            match self.statement() {
+                Ok(statement) => statements.push(statement),
+                Err(error) => {
+                    errors.push(error);
+                    self.synchronize();
+                }
+            }
+        }
+
+        (statements, errors)
     }
 
-    pub fn parse(&mut self) -> Option<Expr> {
-        self.expression()
+    fn statement(&mut self) -> Result<Stmt, LoxError> {
+        // A `///` comment before a statement documents it.
+        let doc = self.peek().doc().map(str::to_string);
+        if self.match_types(vec![PRINT]) {
+            return self.print_statement(doc);
+        }
+        self.expression_statement(doc)
     }
 
-    fn expression(&mut self) -> Option<Expr> {
+    fn print_statement(&mut self, doc: Option<String>) -> Result<Stmt, LoxError> {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        let semicolon = self.consume(SEMICOLON, ErrorCode::L0005, "Expect ';' after value.")?;
+        let span = keyword.span().to(&semicolon.span());
+        Ok(Stmt::Print(value, doc, span))
+    }
+
+    fn expression_statement(&mut self, doc: Option<String>) -> Result<Stmt, LoxError> {
+        let expr = self.expression()?;
+        let semicolon =
+            self.consume(SEMICOLON, ErrorCode::L0005, "Expect ';' after expression.")?;
+        let span = expr.span().to(&semicolon.span());
+        Ok(Stmt::Expression(expr, doc, span))
+    }
+
+    fn expression(&mut self) -> Result<Expr, LoxError> {
         self.equality()
     }
 
-    fn equality(&mut self) -> Option<Expr> {
-        let mut expr = self.comparison();
+    fn equality(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.comparison()?;
 
         while self.match_types(vec![BANG_EQUAL, EQUAL_EQUAL]) {
             let operator = self.previous();
-            let right = self.comparison();
-            expr = Some(Expr::Binary(
-                Box::new(expr.unwrap()),
-                operator,
-                Box::new(right.unwrap()),
-            ));
+            let right = self.comparison()?;
+            expr = Expr::Binary(Box::new(expr), operator, Box::new(right));
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn comparison(&mut self) -> Option<Expr> {
-        let mut expr = self.term();
+    fn comparison(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.term()?;
 
         while self.match_types(vec![GREATER, GREATER_EQUAL, LESS, LESS_EQUAL]) {
             let operator = self.previous();
-            let right = self.term();
-            expr = Some(Expr::Binary(
-                Box::new(expr.unwrap()),
-                operator,
-                Box::new(right.unwrap()),
-            ));
+            let right = self.term()?;
+            expr = Expr::Binary(Box::new(expr), operator, Box::new(right));
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn term(&mut self) -> Option<Expr> {
-        let mut expr = self.factor();
+    fn term(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.factor()?;
 
         while self.match_types(vec![MINUS, PLUS]) {
             let operator = self.previous();
-            let right = self.factor();
-            expr = Some(Expr::Binary(
-                Box::new(expr.unwrap()),
-                operator,
-                Box::new(right.unwrap()),
-            ));
+            let right = self.factor()?;
+            expr = Expr::Binary(Box::new(expr), operator, Box::new(right));
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn factor(&mut self) -> Option<Expr> {
-        let mut expr = self.unary();
+    fn factor(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.unary()?;
 
         while self.match_types(vec![SLASH, STAR]) {
             let operator = self.previous();
-            let right = self.unary();
-            expr = Some(Expr::Binary(
-                Box::new(expr.unwrap()),
-                operator,
-                Box::new(right.unwrap()),
-            ));
+            let right = self.unary()?;
+            expr = Expr::Binary(Box::new(expr), operator, Box::new(right));
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn unary(&mut self) -> Option<Expr> {
+    fn unary(&mut self) -> Result<Expr, LoxError> {
         if self.match_types(vec![BANG, MINUS]) {
             let operator = self.previous();
-            let right = self.unary();
-            return Some(Expr::Unary(operator, Box::new(right.unwrap())));
+            let right = self.nested(Self::unary)?;
+            return Ok(Expr::Unary(operator, Box::new(right)));
         }
         self.primary()
     }
 
-    // TODO: Return Result?
-    fn primary(&mut self) -> Option<Expr> {
+    fn primary(&mut self) -> Result<Expr, LoxError> {
         if self.match_types(vec![FALSE]) {
-            return Some(Expr::Literal("false".to_string()));
+            return Ok(Expr::Literal(Object::Bool(false), self.previous().span()));
         }
         if self.match_types(vec![TRUE]) {
-            return Some(Expr::Literal("true".to_string()));
+            return Ok(Expr::Literal(Object::Bool(true), self.previous().span()));
         }
         if self.match_types(vec![NIL]) {
-            return Some(Expr::Literal("nil".to_string()));
+            return Ok(Expr::Literal(Object::Nil, self.previous().span()));
+        }
+        if !self.at_segment_continuation() && self.match_types(vec![NUMBER, STRING]) {
+            let token = self.previous();
+            let value = token
+                .literal()
+                .expect("Number and string tokens carry a literal")
+                .clone();
+            return Ok(Expr::Literal(value, token.span()));
         }
-        if self.match_types(vec![NUMBER, STRING]) {
-            // TODO: This is a bit of a hack, but it works for now.
-            return Some(Expr::Literal(
-                self.previous()
-                    .literal()
-                    .expect("could not unwrap")
-                    .to_string(),
-            ));
+        if self.match_types(vec![INTERPOLATION]) {
+            return self.interpolation();
         }
         if self.match_types(vec![LEFT_PAREN]) {
-            let expr = self.expression();
-            self.consume(RIGHT_PAREN, "Expect ')' after expression.");
-            return Some(Expr::Grouping(Box::new(expr.unwrap())));
+            let left_paren = self.previous();
+            let expr = self.nested(Self::expression)?;
+            let right_paren = self.consume(
+                RIGHT_PAREN,
+                ErrorCode::L0004,
+                "Expect ')' after expression.",
+            )?;
+            let span = left_paren.span().to(&right_paren.span());
+            return Ok(Expr::Grouping(Box::new(expr), span));
+        }
+        Err(self.error(ErrorCode::L0003, "Expect expression."))
+    }
+
+    /// Runs `parse` one nesting level deeper, failing instead if that would
+    /// pass the nesting limit. `depth` is restored whether or not it succeeds.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, LoxError>,
+    ) -> Result<T, LoxError> {
+        if self.depth >= self.max_depth {
+            return Err(self.error(ErrorCode::L0009, "Expression nested too deeply."));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// The rest of a string containing `${...}`, after its first segment.
+    fn interpolation(&mut self) -> Result<Expr, LoxError> {
+        let start = self.previous();
+        let mut parts = Vec::new();
+        Self::push_segment(&mut parts, &start);
+
+        loop {
+            parts.push(self.nested(Self::expression)?);
+            if !self.at_segment_continuation() {
+                return Err(self.error(
+                    ErrorCode::L0014,
+                    "Expect '}' after interpolated expression.",
+                ));
+            }
+            if self.match_types(vec![INTERPOLATION]) {
+                Self::push_segment(&mut parts, &self.previous());
+                continue;
+            }
+            let end = self.consume(
+                STRING,
+                ErrorCode::L0014,
+                "Expect '}' after interpolated expression.",
+            )?;
+            Self::push_segment(&mut parts, &end);
+            return Ok(Expr::Interpolation(parts, start.span().to(&end.span())));
+        }
+    }
+
+    /// Whether the next token continues an interpolated string after its
+    /// closing `}`, rather than being a string of its own.
+    fn at_segment_continuation(&self) -> bool {
+        matches!(self.peek().token_type(), STRING | INTERPOLATION)
+            && self.peek().lexeme().starts_with('}')
+    }
+
+    fn push_segment(parts: &mut Vec<Expr>, token: &Token) {
+        match token.literal() {
+            Some(Object::Str(text)) if text.is_empty() => {}
+            Some(literal) => parts.push(Expr::Literal(literal.clone(), token.span())),
+            None => unreachable!("String segments carry a literal"),
         }
-        self.error("Expect expression.");
-        None
     }
 
     fn match_types(&mut self, types: Vec<TokenType>) -> bool {
@@ -141,19 +269,44 @@ impl Parser {
         false
     }
 
-    fn consume(&mut self, token_type: TokenType, message: &str) -> Option<Token> {
+    fn consume(
+        &mut self,
+        token_type: TokenType,
+        code: ErrorCode,
+        message: &str,
+    ) -> Result<Token, LoxError> {
         if self.check(token_type) {
-            Some(self.advance())
+            Ok(self.advance())
         } else {
-            self.error(message);
-            None
+            Err(self.error(code, message))
+        }
+    }
+
+    fn error(&self, code: ErrorCode, message: &str) -> LoxError {
+        let error = LoxError::parse(code, self.peek(), message);
+        match self.keyword_typo() {
+            Some((typo, keyword)) => error.with_help(format!(
+                "'{}' is not a keyword; did you mean '{}'?",
+                typo, keyword
+            )),
+            None => error,
         }
     }
 
-    fn error(&mut self, message: &str) {
-        let token = self.peek();
-        error(token, message);
-        panic!();
+    /// A misspelled keyword starting the statement, such as `retrun` or
+    /// `pritn`, along with the keyword it most likely is. Only an error at
+    /// that identifier or right after it counts; anywhere else a keyword
+    /// wasn't expected, so the identifier is more likely a name.
+    fn keyword_typo(&self) -> Option<(&str, &'static str)> {
+        if self.current > self.statement_start + 1 {
+            return None;
+        }
+        let token = &self.tokens[self.statement_start];
+        if *token.token_type() != TokenType::IDENTIFIER {
+            return None;
+        }
+        suggest::closest(token.lexeme(), Scanner::KEYWORDS)
+            .map(|keyword| (token.lexeme().as_str(), keyword))
     }
 
     fn synchronize(&mut self) {
@@ -206,3 +359,192 @@ impl Parser {
         self.tokens[self.current - 1].clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AstPrinter, Scanner};
+
+    fn parse(source: &str) -> (Vec<Stmt>, Vec<LoxError>) {
+        let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+        Parser::new(tokens).parse_partial()
+    }
+
+    fn print(statement: &Stmt) -> String {
+        match statement {
+            Stmt::Expression(expr, _, _) => AstPrinter::new().print(expr),
+            Stmt::Print(expr, _, _) => format!("print {}", AstPrinter::new().print(expr)),
+        }
+    }
+
+    #[test]
+    fn test_statements() {
+        let (statements, errors) = parse("print 1 + 2;\n-3 * 4;");
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            statements.iter().map(print).collect::<Vec<_>>(),
+            vec!["print (+ 1 2)", "(* (- 3) 4)"]
+        );
+    }
+
+    #[test]
+    fn test_reports_every_syntax_error_in_order() {
+        let (statements, errors) = parse("print (1;\nprint 2;\n3 +;\nprint 4\nprint 5;\nprint 6;");
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "[line 1] Error at ';': Expect ')' after expression.",
+                "[line 3] Error at ';': Expect expression.",
+                "[line 5] Error at 'print': Expect ';' after value.",
+            ]
+        );
+        assert_eq!(
+            statements.iter().map(print).collect::<Vec<_>>(),
+            vec!["print 2", "print 6"]
+        );
+    }
+
+    #[test]
+    fn test_suggests_misspelled_keywords() {
+        let (_, errors) = parse("pritn 1;\nretrun;\nbanana;");
+
+        assert_eq!(
+            errors.iter().map(LoxError::help).collect::<Vec<_>>(),
+            vec![
+                Some("'pritn' is not a keyword; did you mean 'print'?"),
+                Some("'retrun' is not a keyword; did you mean 'return'?"),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_suggestion_away_from_statement_start() {
+        let (_, errors) = parse("i 1;\nis 1;\nof 1;\nfoo 1;\nprint 1 + pritn;\nprint (1 retrun);");
+
+        assert_eq!(errors.len(), 6);
+        assert!(errors.iter().all(|error| error.help().is_none()));
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        // Test threads have small stacks, so stay well below the default.
+        let parse = |source: String| {
+            let tokens = Scanner::new(source).scan_tokens().unwrap();
+            let mut parser = Parser::new(tokens);
+            parser.max_depth(32);
+            parser.parse_partial().1
+        };
+
+        let errors = parse(format!("{}1{};", "(".repeat(1000), ")".repeat(1000)));
+        assert_eq!(errors[0].message(), "Expression nested too deeply.");
+        assert_eq!(errors[0].code(), ErrorCode::L0009);
+
+        let errors = parse(format!("print {}1;", "-".repeat(1000)));
+        assert_eq!(errors[0].message(), "Expression nested too deeply.");
+
+        let tokens = Scanner::new("print ((1));\nprint -1 + 2;\nprint (((1)));".to_string())
+            .scan_tokens()
+            .unwrap();
+        let mut parser = Parser::new(tokens);
+        parser.max_depth(2);
+        let (statements, errors) = parser.parse_partial();
+        assert_eq!(statements.len(), 2);
+        assert_eq!(
+            errors[0].to_string(),
+            "[line 3] Error at '1': Expression nested too deeply."
+        );
+    }
+
+    #[test]
+    fn test_long_flat_sum() {
+        let (statements, errors) = parse(&format!("print 1{};", " + 1".repeat(1000)));
+
+        assert!(errors.is_empty());
+        assert_eq!(statements.len(), 1);
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let (statements, errors) =
+            parse("print \"Hello, ${name}! ${1 + 2}\";\nprint \"a${\"b${1}\"}\";\nprint \"${}\";");
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "[line 1] Error at 'name': Expect expression.",
+                "[line 3] Error at '}\"': Expect expression.",
+            ]
+        );
+        assert_eq!(
+            statements.iter().map(print).collect::<Vec<_>>(),
+            vec!["print (interpolate a (interpolate b 1))"]
+        );
+
+        let (statements, errors) = parse("print \"Hi ${1 + 2}!\";");
+        assert!(errors.is_empty());
+        assert_eq!(print(&statements[0]), "print (interpolate Hi  (+ 1 2) !)");
+    }
+
+    #[test]
+    fn test_interpolation_needs_closing_brace() {
+        let (_, errors) = parse("print \"${1 \"x\"}\";");
+
+        assert_eq!(
+            errors[0].to_string(),
+            "[line 1] Error at '\"x\"': Expect '}' after interpolated expression."
+        );
+        assert_eq!(errors[0].code(), ErrorCode::L0014);
+    }
+
+    #[test]
+    fn test_error_at_end() {
+        let tokens = Scanner::new("print".to_string()).scan_tokens().unwrap();
+
+        let errors = Parser::new(tokens).parse().err().unwrap();
+
+        assert_eq!(
+            errors[0].to_string(),
+            "[line 1] Error at end: Expect expression."
+        );
+    }
+
+    #[test]
+    fn test_spans_cover_whole_nodes() {
+        let source = "print -(1 +\n 2) * 3;";
+        let (statements, _) = parse(source);
+        let span = statements[0].span();
+
+        assert_eq!(&source[span.start..span.end], source);
+        let Stmt::Print(expr, _, _) = &statements[0] else {
+            panic!("Expected a print statement");
+        };
+        let span = expr.span();
+        assert_eq!(&source[span.start..span.end], "-(1 +\n 2) * 3");
+        assert_eq!((span.line, span.column), (1, 7));
+    }
+
+    #[test]
+    fn test_doc_comments_attach_to_statements() {
+        let (statements, errors) = parse(
+            "/// Prints one.\n/// Then two.\nprint 1;\n1 + /// not here\n2;\n/// Adds.\n3 + 4;",
+        );
+
+        assert!(errors.is_empty());
+        let docs: Vec<_> = statements
+            .iter()
+            .map(|statement| match statement {
+                Stmt::Expression(_, doc, _) | Stmt::Print(_, doc, _) => doc.as_deref(),
+            })
+            .collect();
+        assert_eq!(docs, [Some("Prints one.\nThen two."), None, Some("Adds.")]);
+    }
+}
@@ -3,24 +3,63 @@
 //
 // This file is part of rlox-ast
 
-use crate::ast_printer::AstPrinter;
+use crate::diagnostics::{Diagnostics, ErrorFormat};
+use crate::interpreter::Interpreter;
+use crate::linter::Linter;
+use crate::{ErrorCode, LoxError};
 use std::io::Write;
 
 pub struct Runner {
-    had_error: bool,
+    diagnostics: Diagnostics,
+    deny_warnings: bool,
+    error_format: ErrorFormat,
+    max_depth: Option<usize>,
 }
 
 impl Runner {
     pub fn new() -> Self {
-        Self { had_error: false }
+        Self {
+            diagnostics: Diagnostics::new(),
+            deny_warnings: false,
+            error_format: ErrorFormat::Human,
+            max_depth: None,
+        }
+    }
+
+    /// Overrides the parser's nesting limit and the interpreter's depth limit.
+    pub fn max_depth(&mut self, max_depth: usize) {
+        self.max_depth = Some(max_depth);
+    }
+
+    pub fn error_format(&mut self, format: ErrorFormat) {
+        self.error_format = format;
+    }
+
+    /// Treat warnings as errors: report them, don't run the program, and
+    /// exit with 65.
+    pub fn deny_warnings(&mut self, deny: bool) {
+        self.deny_warnings = deny;
     }
+
     pub fn run_file(&mut self, path: &String) {
-        let source = std::fs::read_to_string(path).expect("Failed to read file");
-        self.run(source);
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(error) => {
+                let message = format!("Could not read \"{}\": {}", path, error);
+                self.diagnostics
+                    .report(LoxError::io(ErrorCode::L0019, &message));
+                self.diagnostics.emit("", Some(path), self.error_format);
+                std::process::exit(66);
+            }
+        };
+        self.run(source, Some(path));
 
-        if self.had_error {
+        if self.diagnostics.had_error() || (self.deny_warnings && self.diagnostics.had_warning()) {
             std::process::exit(65);
         }
+        if self.diagnostics.had_runtime_error() {
+            std::process::exit(70);
+        }
     }
 
     pub fn run_prompt(&mut self) {
@@ -29,23 +68,46 @@ impl Runner {
             let mut line = String::new();
             std::io::stdout().flush().expect("Failed to flush stdout");
             match std::io::stdin().read_line(&mut line) {
-                Ok(_) => self.run(line),
-                _ => break,
+                Ok(0) | Err(_) => break,
+                Ok(_) => self.run(line, None),
             }
+            // A mistake on one line shouldn't end the session.
+            self.diagnostics.clear();
         }
     }
 
-    fn run(&mut self, source: String) {
-        let mut scanner = crate::Scanner::new(source);
-        let tokens = scanner.scan_tokens();
+    fn run(&mut self, source: String, file: Option<&str>) {
+        self.execute(&source);
+        self.diagnostics.emit(&source, file, self.error_format);
+    }
+
+    fn execute(&mut self, source: &str) {
+        // The parser takes owned tokens, so BorrowedScanner wouldn't save
+        // anything here; borrowing only avoids copying the whole source.
+        let mut scanner = crate::Scanner::borrowed(source);
+        let tokens = match scanner.scan_tokens() {
+            Ok(tokens) => tokens,
+            Err(errors) => return self.diagnostics.extend(errors),
+        };
         let mut parser = crate::Parser::new(tokens);
-        let expr = parser.parse();
+        let mut interpreter = Interpreter::new();
+        if let Some(max_depth) = self.max_depth {
+            parser.max_depth(max_depth);
+            interpreter.max_depth(max_depth);
+        }
+        let statements = match parser.parse() {
+            Ok(statements) => statements,
+            Err(errors) => return self.diagnostics.extend(errors),
+        };
 
-        if self.had_error {
+        self.diagnostics.extend(Linter::new().check(&statements));
+        if self.deny_warnings && self.diagnostics.had_warning() {
             return;
         }
-        let ast_printer = AstPrinter::new();
-        println!("{}", ast_printer.print(&expr.unwrap()));
+
+        if let Err(error) = interpreter.interpret(&statements) {
+            self.diagnostics.report(error);
+        }
     }
 }
 
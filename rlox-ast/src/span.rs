@@ -0,0 +1,26 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of rlox-ast
+
+use crate::{Expr, Span, Stmt};
+
+impl Expr {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Binary(left, _, right) => left.span().to(&right.span()),
+            Self::Grouping(_, span) | Self::Interpolation(_, span) | Self::Literal(_, span) => {
+                *span
+            }
+            Self::Unary(operator, right) => operator.span().to(&right.span()),
+        }
+    }
+}
+
+impl Stmt {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Expression(_, _, span) | Self::Print(_, _, span) => *span,
+        }
+    }
+}
@@ -0,0 +1,32 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of rlox-ast
+
+use crate::Expr;
+use crate::Span;
+
+pub enum Stmt {
+    Expression(Expr, Option<String>, Span),
+    Print(Expr, Option<String>, Span),
+}
+
+pub trait Visitor<T> {
+    fn visit_expression_stmt(&self, expression: &Expr, doc: &Option<String>, span: &Span) -> T;
+    fn visit_print_stmt(&self, expression: &Expr, doc: &Option<String>, span: &Span) -> T;
+}
+
+pub trait Accept<T> {
+    fn accept<V: Visitor<T>>(&self, visitor: &V) -> T;
+}
+
+impl<T> Accept<T> for Stmt {
+    fn accept<V: Visitor<T>>(&self, visitor: &V) -> T {
+        match self {
+            Self::Expression(expression, doc, span) => {
+                visitor.visit_expression_stmt(expression, doc, span)
+            }
+            Self::Print(expression, doc, span) => visitor.visit_print_stmt(expression, doc, span),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of rlox-ast
+
+/// Names shorter than this get no suggestion: one edit turns `i` into `if`
+/// or `foo` into `for`, and such names are far more often meant as written.
+const MIN_LENGTH: usize = 4;
+
+/// The candidate closest to `name`, if any is close enough to plausibly be
+/// what was meant. "Close enough" allows one edit per three characters, with
+/// swapping two neighbouring characters counting as a single edit.
+///
+/// Used for misspelled keywords; the candidates can as well be the names in
+/// scope, for suggesting a fix for an undefined variable once there are any.
+pub(crate) fn closest<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let length = name.chars().count();
+    if length < MIN_LENGTH {
+        return None;
+    }
+    let limit = length / 3;
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Optimal string alignment distance: the Levenshtein distance extended
+/// with transpositions of adjacent characters.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // Three rows of the usual dynamic programming table.
+    let mut before_previous = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before_previous[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut before_previous, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("print", ""), 5);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("retrun", "return"), 1);
+        assert_eq!(edit_distance("fucn", "fun"), 1);
+        assert_eq!(edit_distance("wihle", "while"), 1);
+    }
+
+    #[test]
+    fn test_closest() {
+        let keywords = ["fun", "for", "print", "return", "while"];
+
+        assert_eq!(closest("pritn", keywords), Some("print"));
+        assert_eq!(closest("retrun", keywords), Some("return"));
+        assert_eq!(closest("fucn", keywords), Some("fun"));
+        assert_eq!(closest("banana", keywords), None);
+        assert_eq!(closest("print", keywords), None);
+    }
+
+    #[test]
+    fn test_closest_ignores_short_names() {
+        let keywords = ["and", "for", "fun", "if", "or", "var"];
+
+        for name in ["i", "is", "of", "foo", "va"] {
+            assert_eq!(closest(name, keywords), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_closest_variable() {
+        let names = ["count", "total", "index"];
+
+        assert_eq!(closest("coutn", names), Some("count"));
+        assert_eq!(closest("totl", names), Some("total"));
+        assert_eq!(closest("banana", names), None);
+    }
+}
@@ -0,0 +1,410 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of rlox-bytecode
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::chunk::{Chunk, OpCode};
+use crate::value::Value;
+
+/// A position in the code that jumps can refer to before it is known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Label(usize);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    TooManyConstants,
+    JumpTooLarge { offset: usize },
+    LabelBoundTwice(Label),
+    UnboundLabel(Label),
+    JumpToBoundLabel { offset: usize, label: Label },
+    LoopToUnboundLabel { offset: usize, label: Label },
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyConstants => write!(f, "Too many constants in one chunk."),
+            Self::JumpTooLarge { offset } => {
+                write!(f, "Too much code to jump over at offset {}.", offset)
+            }
+            Self::LabelBoundTwice(label) => write!(f, "Label {} is bound twice.", label.0),
+            Self::UnboundLabel(label) => write!(f, "Label {} is never bound.", label.0),
+            Self::JumpToBoundLabel { offset, label } => write!(
+                f,
+                "Jump at offset {} targets label {} behind it; use a loop.",
+                offset, label.0
+            ),
+            Self::LoopToUnboundLabel { offset, label } => write!(
+                f,
+                "Loop at offset {} targets label {} before it is bound.",
+                offset, label.0
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Assembles a [`Chunk`] one instruction at a time.
+///
+/// Forward jumps are written with a placeholder distance and patched once
+/// their label is bound. The first error is kept and returned from
+/// [`ChunkBuilder::build`] so emit calls can be chained.
+#[derive(Default)]
+pub struct ChunkBuilder {
+    chunk: Chunk,
+    labels: Vec<Option<usize>>,
+    // Offset of each placeholder operand and the label it should jump to.
+    pending_jumps: Vec<(usize, Label)>,
+    error: Option<BuildError>,
+}
+
+impl ChunkBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current end of the code, where the next byte will be written.
+    pub fn offset(&self) -> usize {
+        self.chunk.len()
+    }
+
+    pub fn emit_byte(&mut self, byte: u8, line: i32) -> &mut Self {
+        self.chunk.write_chunk(byte, line);
+        self
+    }
+
+    pub fn emit_constant(&mut self, value: Value, line: i32) -> &mut Self {
+        let index = self.chunk.add_constant(value);
+        let index = match u8::try_from(index) {
+            Ok(index) => index,
+            Err(_) => {
+                self.fail(BuildError::TooManyConstants);
+                0
+            }
+        };
+        self.emit_byte(OpCode::OP_CONSTANT.value(), line)
+            .emit_byte(index, line)
+    }
+
+    pub fn emit_return(&mut self, line: i32) -> &mut Self {
+        self.emit_byte(OpCode::OP_RETURN.value(), line)
+    }
+
+    /// Emits an instruction that has no operands, like `OP_ADD`.
+    pub fn emit_op(&mut self, opcode: OpCode, line: i32) -> &mut Self {
+        debug_assert_eq!(opcode.operand_bytes(), 0);
+        self.emit_byte(opcode.value(), line)
+    }
+
+    /// Emits an `OP_JUMP` to `label`, which must be bound later on.
+    pub fn emit_jump(&mut self, label: Label, line: i32) -> &mut Self {
+        self.emit_byte(OpCode::OP_JUMP.value(), line);
+        self.pending_jumps.push((self.offset(), label));
+        self.emit_byte(0xff, line).emit_byte(0xff, line)
+    }
+
+    /// Emits an `OP_LOOP` back to `label`, which must already be bound.
+    pub fn emit_loop(&mut self, label: Label, line: i32) -> &mut Self {
+        let offset = self.offset();
+        self.emit_byte(OpCode::OP_LOOP.value(), line);
+
+        let distance = match self.labels[label.0] {
+            Some(target) => self.distance(offset, offset + 3 - target),
+            None => {
+                self.fail(BuildError::LoopToUnboundLabel { offset, label });
+                0
+            }
+        };
+        let [high, low] = distance.to_be_bytes();
+        self.emit_byte(high, line).emit_byte(low, line)
+    }
+
+    pub fn new_label(&mut self) -> Label {
+        self.labels.push(None);
+        Label(self.labels.len() - 1)
+    }
+
+    /// Binds `label` to the current offset.
+    pub fn bind(&mut self, label: Label) -> &mut Self {
+        if self.labels[label.0].is_some() {
+            self.fail(BuildError::LabelBoundTwice(label));
+        } else {
+            self.labels[label.0] = Some(self.offset());
+        }
+        self
+    }
+
+    pub fn build(mut self) -> Result<Chunk, BuildError> {
+        for (operand, label) in std::mem::take(&mut self.pending_jumps) {
+            // A jump only goes forward, so its label must be bound after it.
+            let target = match self.labels[label.0] {
+                Some(target) if target >= operand + 2 => target,
+                Some(_) => {
+                    let offset = operand - 1;
+                    self.fail(BuildError::JumpToBoundLabel { offset, label });
+                    continue;
+                }
+                None => {
+                    self.fail(BuildError::UnboundLabel(label));
+                    continue;
+                }
+            };
+            let distance = self.distance(operand - 1, target - (operand + 2));
+            let [high, low] = distance.to_be_bytes();
+            self.chunk.patch(operand, high);
+            self.chunk.patch(operand + 1, low);
+        }
+
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.chunk),
+        }
+    }
+
+    fn distance(&mut self, offset: usize, distance: usize) -> u16 {
+        u16::try_from(distance).unwrap_or_else(|_| {
+            self.fail(BuildError::JumpTooLarge { offset });
+            0
+        })
+    }
+
+    fn fail(&mut self, error: BuildError) {
+        self.error.get_or_insert(error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{DecodeError, Instruction, Operand};
+
+    /// Small xorshift generator so the property tests are reproducible
+    /// without pulling in a dependency.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+    }
+
+    #[test]
+    fn test_every_byte_and_constant_is_retrievable() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..100 {
+            let mut chunk = Chunk::new();
+            let mut bytes = Vec::new();
+            let mut constants = Vec::new();
+
+            for _ in 0..rng.below(1000) {
+                if rng.below(2) == 0 {
+                    let byte = rng.below(256) as u8;
+                    let line = rng.below(10_000) as i32;
+                    chunk.write_chunk(byte, line);
+                    bytes.push((byte, line));
+                } else {
+                    let value = f64::from_bits(rng.next());
+                    let index = chunk.add_constant(value);
+                    assert_eq!(index, constants.len());
+                    constants.push(value);
+                }
+            }
+
+            assert_eq!(chunk.len(), bytes.len());
+            for (offset, (byte, line)) in bytes.iter().enumerate() {
+                assert_eq!(chunk.code()[offset], *byte);
+                assert_eq!(chunk.line(offset), Some(*line));
+            }
+            assert_eq!(chunk.constants().len(), constants.len());
+            for (stored, written) in chunk.constants().iter().zip(&constants) {
+                assert_eq!(stored.to_bits(), written.to_bits());
+            }
+        }
+    }
+
+    #[test]
+    fn test_jumps_land_on_their_labels() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..100 {
+            let mut builder = ChunkBuilder::new();
+            let labels: Vec<Label> = (0..8).map(|_| builder.new_label()).collect();
+            let mut bound = vec![false; labels.len()];
+            let mut constants = 0;
+
+            for _ in 0..rng.below(200) {
+                let label = rng.below(labels.len() as u64) as usize;
+                match rng.below(4) {
+                    0 if constants < 256 => {
+                        builder.emit_constant(rng.below(1000) as f64, 1);
+                        constants += 1;
+                    }
+                    1 if !bound[label] => {
+                        builder.bind(labels[label]);
+                        bound[label] = true;
+                    }
+                    2 if bound[label] => {
+                        builder.emit_loop(labels[label], 1);
+                    }
+                    3 if !bound[label] => {
+                        builder.emit_jump(labels[label], 1);
+                    }
+                    _ => {
+                        builder.emit_return(1);
+                    }
+                }
+            }
+            let mut targets = Vec::new();
+            for (label, bound) in labels.iter().zip(&bound) {
+                targets.push(if *bound {
+                    builder.labels[label.0].unwrap()
+                } else {
+                    builder.bind(*label);
+                    builder.offset()
+                });
+            }
+
+            let chunk = builder.build().unwrap();
+
+            let mut expected_offset = 0;
+            for instruction in chunk.instructions() {
+                let instruction = instruction.unwrap();
+                assert_eq!(instruction.offset, expected_offset);
+                expected_offset = instruction.next_offset();
+                if let [Operand::Jump { target, .. }] = instruction.operands.as_slice() {
+                    assert!(targets.contains(target));
+                }
+            }
+            assert_eq!(expected_offset, chunk.len());
+        }
+    }
+
+    #[test]
+    fn test_forward_jump_and_loop() {
+        let mut builder = ChunkBuilder::new();
+        let start = builder.new_label();
+        let end = builder.new_label();
+        builder
+            .bind(start)
+            .emit_jump(end, 1)
+            .emit_loop(start, 2)
+            .bind(end)
+            .emit_return(3);
+
+        let chunk = builder.build().unwrap();
+        let instructions: Vec<_> = chunk.instructions().collect();
+
+        assert_eq!(
+            instructions,
+            vec![
+                Ok(Instruction {
+                    offset: 0,
+                    line: 1,
+                    opcode: OpCode::OP_JUMP,
+                    operands: vec![Operand::Jump {
+                        distance: 3,
+                        target: 6
+                    }],
+                }),
+                Ok(Instruction {
+                    offset: 3,
+                    line: 2,
+                    opcode: OpCode::OP_LOOP,
+                    operands: vec![Operand::Jump {
+                        distance: 6,
+                        target: 0
+                    }],
+                }),
+                Ok(Instruction {
+                    offset: 6,
+                    line: 3,
+                    opcode: OpCode::OP_RETURN,
+                    operands: vec![],
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let mut builder = ChunkBuilder::new();
+        let label = builder.new_label();
+        builder.emit_jump(label, 1);
+        assert_eq!(builder.build().err(), Some(BuildError::UnboundLabel(label)));
+
+        let mut builder = ChunkBuilder::new();
+        let label = builder.new_label();
+        builder.bind(label).emit_return(1).emit_jump(label, 1);
+        assert_eq!(
+            builder.build().err(),
+            Some(BuildError::JumpToBoundLabel { offset: 1, label })
+        );
+
+        // Bound at the jump itself, so the placeholder would jump backwards.
+        let mut builder = ChunkBuilder::new();
+        let label = builder.new_label();
+        builder.bind(label).emit_jump(label, 1).emit_return(1);
+        assert_eq!(
+            builder.build().err(),
+            Some(BuildError::JumpToBoundLabel { offset: 0, label })
+        );
+
+        let mut builder = ChunkBuilder::new();
+        let label = builder.new_label();
+        builder.emit_loop(label, 1);
+        assert_eq!(
+            builder.build().err(),
+            Some(BuildError::LoopToUnboundLabel { offset: 0, label })
+        );
+
+        let mut builder = ChunkBuilder::new();
+        let label = builder.new_label();
+        builder.bind(label).bind(label);
+        assert_eq!(
+            builder.build().err(),
+            Some(BuildError::LabelBoundTwice(label))
+        );
+
+        let mut builder = ChunkBuilder::new();
+        for value in 0..257 {
+            builder.emit_constant(value as f64, 1);
+        }
+        assert_eq!(builder.build().err(), Some(BuildError::TooManyConstants));
+
+        let mut builder = ChunkBuilder::new();
+        let label = builder.new_label();
+        builder.emit_jump(label, 1);
+        for _ in 0..=u16::MAX {
+            builder.emit_return(1);
+        }
+        builder.bind(label);
+        assert_eq!(
+            builder.build().err(),
+            Some(BuildError::JumpTooLarge { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn test_out_of_range_loop_does_not_decode() {
+        let mut chunk = Chunk::new();
+        chunk.write_chunk(OpCode::OP_LOOP.value(), 1);
+        chunk.write_chunk(0, 1);
+        chunk.write_chunk(9, 1);
+
+        assert_eq!(
+            chunk.instructions().collect::<Vec<_>>(),
+            vec![Err(DecodeError::JumpOutOfRange { offset: 0 })]
+        );
+    }
+}
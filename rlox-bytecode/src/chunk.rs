@@ -3,13 +3,22 @@
 //
 // This file is part of rlox-bytecode
 
-use crate::grow_capacity;
+use std::fmt::{self, Display, Formatter};
+
 use crate::value::{Value, ValueArray};
 
 #[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OpCode {
     OP_CONSTANT,
     OP_RETURN,
+    OP_JUMP,
+    OP_LOOP,
+    OP_NEGATE,
+    OP_ADD,
+    OP_SUBTRACT,
+    OP_MULTIPLY,
+    OP_DIVIDE,
 }
 
 impl OpCode {
@@ -17,14 +26,132 @@ impl OpCode {
         match self {
             Self::OP_CONSTANT => 0,
             Self::OP_RETURN => 1,
+            Self::OP_JUMP => 2,
+            Self::OP_LOOP => 3,
+            Self::OP_NEGATE => 4,
+            Self::OP_ADD => 5,
+            Self::OP_SUBTRACT => 6,
+            Self::OP_MULTIPLY => 7,
+            Self::OP_DIVIDE => 8,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::OP_CONSTANT => "OP_CONSTANT",
+            Self::OP_RETURN => "OP_RETURN",
+            Self::OP_JUMP => "OP_JUMP",
+            Self::OP_LOOP => "OP_LOOP",
+            Self::OP_NEGATE => "OP_NEGATE",
+            Self::OP_ADD => "OP_ADD",
+            Self::OP_SUBTRACT => "OP_SUBTRACT",
+            Self::OP_MULTIPLY => "OP_MULTIPLY",
+            Self::OP_DIVIDE => "OP_DIVIDE",
+        }
+    }
+
+    /// Number of operand bytes that follow the opcode in the code stream.
+    pub fn operand_bytes(&self) -> usize {
+        match self {
+            Self::OP_CONSTANT => 1,
+            Self::OP_RETURN
+            | Self::OP_NEGATE
+            | Self::OP_ADD
+            | Self::OP_SUBTRACT
+            | Self::OP_MULTIPLY
+            | Self::OP_DIVIDE => 0,
+            Self::OP_JUMP | Self::OP_LOOP => 2,
+        }
+    }
+}
+
+impl TryFrom<u8> for OpCode {
+    type Error = u8;
+
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        match byte {
+            0 => Ok(Self::OP_CONSTANT),
+            1 => Ok(Self::OP_RETURN),
+            2 => Ok(Self::OP_JUMP),
+            3 => Ok(Self::OP_LOOP),
+            4 => Ok(Self::OP_NEGATE),
+            5 => Ok(Self::OP_ADD),
+            6 => Ok(Self::OP_SUBTRACT),
+            7 => Ok(Self::OP_MULTIPLY),
+            8 => Ok(Self::OP_DIVIDE),
+            _ => Err(byte),
+        }
+    }
+}
+
+/// A decoded operand of an instruction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operand {
+    Constant {
+        index: u8,
+        value: Value,
+    },
+    /// The raw 16-bit distance and the offset it lands on.
+    Jump {
+        distance: u16,
+        target: usize,
+    },
+}
+
+/// A single decoded instruction along with where it lives in the chunk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub offset: usize,
+    pub line: i32,
+    pub opcode: OpCode,
+    pub operands: Vec<Operand>,
+}
+
+impl Instruction {
+    /// Offset of the instruction that follows this one.
+    pub fn next_offset(&self) -> usize {
+        self.offset + 1 + self.opcode.operand_bytes()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    UnknownOpcode { offset: usize, byte: u8 },
+    Truncated { offset: usize, opcode: OpCode },
+    ConstantOutOfRange { offset: usize, index: u8 },
+    JumpOutOfRange { offset: usize },
+}
+
+impl DecodeError {
+    pub fn offset(&self) -> usize {
+        match self {
+            Self::UnknownOpcode { offset, .. }
+            | Self::Truncated { offset, .. }
+            | Self::ConstantOutOfRange { offset, .. }
+            | Self::JumpOutOfRange { offset } => *offset,
         }
     }
 }
 
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownOpcode { byte, .. } => write!(f, "Unknown opcode {}", byte),
+            Self::Truncated { opcode, .. } => {
+                write!(f, "{} is missing its operands", opcode.name())
+            }
+            Self::ConstantOutOfRange { index, .. } => {
+                write!(f, "Constant index {} is out of range", index)
+            }
+            Self::JumpOutOfRange { .. } => write!(f, "Jump lands before the start of the chunk"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
 #[derive(Default)]
 pub struct Chunk {
-    count: i32,
-    capacity: i32,
     code: Vec<u8>,
     lines: Vec<i32>,
     constants: ValueArray,
@@ -33,71 +160,294 @@ pub struct Chunk {
 impl Chunk {
     pub fn new() -> Self {
         Self {
-            count: 0,
-            capacity: 0,
             code: Vec::new(),
             lines: Vec::new(),
             constants: ValueArray::new(),
         }
     }
 
-    pub fn add_constant(&mut self, value: Value) -> i32 {
+    /// Adds `value` to the constant pool and returns its index.
+    pub fn add_constant(&mut self, value: Value) -> usize {
         self.constants.write_value(value);
-        self.constants.count - 1
+        self.constants.len() - 1
     }
 
     pub fn write_chunk(&mut self, byte: u8, line: i32) {
-        if self.capacity < self.count + 1 {
-            let old_capacity = self.capacity;
-            self.capacity = grow_capacity(old_capacity);
+        self.code.push(byte);
+        self.lines.push(line);
+    }
+
+    /// Overwrites an already written byte, keeping its line.
+    pub(crate) fn patch(&mut self, offset: usize, byte: u8) {
+        self.code[offset] = byte;
+    }
+
+    /// Number of bytes of code in the chunk.
+    pub fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+
+    pub fn code(&self) -> &[u8] {
+        &self.code
+    }
+
+    pub fn constants(&self) -> &[Value] {
+        &self.constants.values
+    }
+
+    /// Source line of the byte at `offset`, if there is one.
+    pub fn line(&self, offset: usize) -> Option<i32> {
+        self.lines.get(offset).copied()
+    }
+
+    /// Iterates over the decoded instructions of the chunk in order.
+    pub fn instructions(&self) -> Instructions<'_> {
+        Instructions {
+            chunk: self,
+            offset: 0,
+        }
+    }
+
+    /// Decodes the instruction starting at `offset`.
+    pub fn decode(&self, offset: usize) -> Result<Instruction, DecodeError> {
+        let byte = self.code[offset];
+        let opcode =
+            OpCode::try_from(byte).map_err(|byte| DecodeError::UnknownOpcode { offset, byte })?;
+        if offset + opcode.operand_bytes() >= self.len() {
+            return Err(DecodeError::Truncated { offset, opcode });
         }
 
-        self.code.insert(self.count as usize, byte);
-        self.lines.insert(self.count as usize, line);
-        self.count += 1;
+        let operands = match opcode {
+            OpCode::OP_CONSTANT => {
+                let index = self.code[offset + 1];
+                let value = *self
+                    .constants
+                    .values
+                    .get(index as usize)
+                    .ok_or(DecodeError::ConstantOutOfRange { offset, index })?;
+                vec![Operand::Constant { index, value }]
+            }
+            OpCode::OP_RETURN
+            | OpCode::OP_NEGATE
+            | OpCode::OP_ADD
+            | OpCode::OP_SUBTRACT
+            | OpCode::OP_MULTIPLY
+            | OpCode::OP_DIVIDE => Vec::new(),
+            OpCode::OP_JUMP | OpCode::OP_LOOP => {
+                let distance = u16::from_be_bytes([self.code[offset + 1], self.code[offset + 2]]);
+                let after = offset + 3;
+                let target = if opcode == OpCode::OP_JUMP {
+                    Some(after + distance as usize)
+                } else {
+                    after.checked_sub(distance as usize)
+                };
+                let target = target.ok_or(DecodeError::JumpOutOfRange { offset })?;
+                vec![Operand::Jump { distance, target }]
+            }
+        };
+
+        Ok(Instruction {
+            offset,
+            line: self.lines[offset],
+            opcode,
+            operands,
+        })
     }
 
     pub fn disassemble_chunk(&self, name: &str) {
-        println!("== {} ==", name);
+        let mut out = String::new();
+        self.disassemble(name, &mut out)
+            .expect("Writing to a String cannot fail");
+        print!("{}", out);
+    }
+
+    /// Writes a human readable listing of the whole chunk to `out`.
+    pub fn disassemble<W: fmt::Write>(&self, name: &str, out: &mut W) -> fmt::Result {
+        writeln!(out, "== {} ==", name)?;
 
         let mut offset = 0;
-        while offset < self.count {
-            offset = self.disassemble_instruction(offset);
+        while offset < self.len() {
+            offset = self.disassemble_instruction(offset, out)?;
         }
+        Ok(())
     }
 
-    fn disassemble_instruction(&self, offset: i32) -> i32 {
-        print!("{:04} ", offset);
-        if offset > 0 && self.lines[offset as usize] == self.lines[offset as usize - 1] {
-            print!("   | ");
+    /// Same as [`Chunk::disassemble`] but for byte oriented sinks like files or stderr.
+    pub fn write_disassembly<W: std::io::Write>(
+        &self,
+        name: &str,
+        out: &mut W,
+    ) -> std::io::Result<()> {
+        let mut listing = String::new();
+        self.disassemble(name, &mut listing)
+            .expect("Writing to a String cannot fail");
+        out.write_all(listing.as_bytes())
+    }
+
+    /// Writes the instruction at `offset` to `out` and returns the offset of the next one.
+    pub fn disassemble_instruction<W: fmt::Write>(
+        &self,
+        offset: usize,
+        out: &mut W,
+    ) -> Result<usize, fmt::Error> {
+        write!(out, "{:04} ", offset)?;
+        if offset > 0 && self.lines[offset] == self.lines[offset - 1] {
+            write!(out, "   | ")?;
         } else {
-            print!("{:4} ", self.lines[offset as usize]);
+            write!(out, "{:4} ", self.lines[offset])?;
         }
 
-        let instruction = self.code[offset as usize];
-        match instruction {
-            0 => self.constant_instruction("OP_CONSTANT", offset),
-            1 => self.simple_instruction("OP_RETURN", offset),
-            _ => {
-                println!("Unknown opcode {}", instruction);
-
-                offset + 1
+        match self.decode(offset) {
+            Ok(instruction) => {
+                let name = instruction.opcode.name();
+                match instruction.operands.as_slice() {
+                    [Operand::Constant { index, value }] => {
+                        writeln!(out, "{:<16} {:4} '{}'", name, index, value)?
+                    }
+                    [Operand::Jump { target, .. }] => {
+                        writeln!(out, "{:<16} {:4} -> {}", name, offset, target)?
+                    }
+                    _ => writeln!(out, "{}", name)?,
+                }
+                Ok(instruction.next_offset())
             }
+            Err(error) => {
+                writeln!(out, "{}", error)?;
+                Ok(self.resume_offset(&error))
+            }
+        }
+    }
+
+    /// Where decoding picks up again after `error`, so one bad instruction
+    /// doesn't hide the rest of the chunk.
+    fn resume_offset(&self, error: &DecodeError) -> usize {
+        match error {
+            DecodeError::UnknownOpcode { offset, .. } => offset + 1,
+            DecodeError::ConstantOutOfRange { offset, .. } => offset + 2,
+            DecodeError::JumpOutOfRange { offset } => offset + 3,
+            DecodeError::Truncated { .. } => self.len(),
         }
     }
+}
+
+pub struct Instructions<'a> {
+    chunk: &'a Chunk,
+    offset: usize,
+}
+
+impl Iterator for Instructions<'_> {
+    type Item = Result<Instruction, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.chunk.len() {
+            return None;
+        }
+
+        let decoded = self.chunk.decode(self.offset);
+        self.offset = match &decoded {
+            Ok(instruction) => instruction.next_offset(),
+            Err(error) => self.chunk.resume_offset(error),
+        };
+        Some(decoded)
+    }
+}
 
-    fn simple_instruction(&self, name: &str, offset: i32) -> i32 {
-        println!("{}", name);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        offset + 1
+    fn test_chunk() -> Chunk {
+        let mut chunk = Chunk::new();
+        let constant = chunk.add_constant(1.2);
+        chunk.write_chunk(OpCode::OP_CONSTANT.value(), 123);
+        chunk.write_chunk(constant as u8, 123);
+        chunk.write_chunk(OpCode::OP_RETURN.value(), 124);
+        chunk
     }
 
-    fn constant_instruction(&self, name: &str, offset: i32) -> i32 {
-        let constant = self.code[offset as usize + 1];
-        print!("{:<16} {:4} '", name, constant);
-        print!("{}", self.constants.values[constant as usize]);
-        println!("'");
+    #[test]
+    fn test_disassemble() {
+        let mut out = String::new();
+        test_chunk().disassemble("test chunk", &mut out).unwrap();
+
+        assert_eq!(
+            out,
+            "== test chunk ==\n\
+             0000  123 OP_CONSTANT         0 '1.2'\n\
+             0002  124 OP_RETURN\n"
+        );
+    }
+
+    #[test]
+    fn test_disassemble_same_line() {
+        let mut chunk = test_chunk();
+        chunk.write_chunk(OpCode::OP_RETURN.value(), 124);
+        let mut out = Vec::new();
+        chunk.write_disassembly("lines", &mut out).unwrap();
+
+        assert!(
+            String::from_utf8(out)
+                .unwrap()
+                .ends_with("0003    | OP_RETURN\n")
+        );
+    }
+
+    #[test]
+    fn test_instructions() {
+        let instructions: Vec<_> = test_chunk().instructions().collect();
+
+        assert_eq!(
+            instructions,
+            vec![
+                Ok(Instruction {
+                    offset: 0,
+                    line: 123,
+                    opcode: OpCode::OP_CONSTANT,
+                    operands: vec![Operand::Constant {
+                        index: 0,
+                        value: 1.2
+                    }],
+                }),
+                Ok(Instruction {
+                    offset: 2,
+                    line: 124,
+                    opcode: OpCode::OP_RETURN,
+                    operands: vec![],
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_instructions() {
+        let mut chunk = Chunk::new();
+        chunk.write_chunk(42, 1);
+        chunk.write_chunk(OpCode::OP_CONSTANT.value(), 1);
+        chunk.write_chunk(7, 1);
+        chunk.write_chunk(OpCode::OP_CONSTANT.value(), 2);
+
+        let instructions: Vec<_> = chunk.instructions().collect();
 
-        offset + 2
+        assert_eq!(
+            instructions,
+            vec![
+                Err(DecodeError::UnknownOpcode {
+                    offset: 0,
+                    byte: 42
+                }),
+                Err(DecodeError::ConstantOutOfRange {
+                    offset: 1,
+                    index: 7
+                }),
+                Err(DecodeError::Truncated {
+                    offset: 3,
+                    opcode: OpCode::OP_CONSTANT
+                }),
+            ]
+        );
     }
 }
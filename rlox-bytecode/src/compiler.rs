@@ -0,0 +1,307 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of rlox-bytecode
+
+//! A single-pass Pratt compiler from Lox source to a [`Chunk`].
+//!
+//! Values are only numbers so far, so the compiler handles one arithmetic
+//! expression, like the book's compiler before statements are added. The
+//! chunk evaluates the expression and returns, which prints its value.
+
+use lox_syntax::{ErrorCode, LoxError, Object, Scanner, Token, TokenType};
+
+use crate::builder::ChunkBuilder;
+use crate::chunk::{Chunk, OpCode};
+
+/// How deeply groupings and unary operators may nest, as in rlox-ast.
+const MAX_DEPTH: usize = 256;
+
+/// Compiles `source`, a single expression, into a chunk that prints its value.
+pub fn compile(source: &str) -> Result<Chunk, Vec<LoxError>> {
+    let tokens = Scanner::borrowed(source).scan_tokens()?;
+    let compiler = Compiler {
+        tokens,
+        current: 0,
+        depth: 0,
+        builder: ChunkBuilder::new(),
+    };
+    compiler.compile().map_err(|error| vec![error])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Equality,
+    Comparison,
+    Term,
+    Factor,
+    Unary,
+}
+
+impl Precedence {
+    /// The precedence one level tighter, for the right operand of a
+    /// left-associative operator.
+    fn next(self) -> Self {
+        match self {
+            Self::Equality => Self::Comparison,
+            Self::Comparison => Self::Term,
+            Self::Term => Self::Factor,
+            Self::Factor | Self::Unary => Self::Unary,
+        }
+    }
+
+    /// The precedence of `token_type` as a binary operator, if it is one.
+    fn of_infix(token_type: &TokenType) -> Option<Self> {
+        match token_type {
+            TokenType::EQUAL_EQUAL | TokenType::BANG_EQUAL => Some(Self::Equality),
+            TokenType::GREATER
+            | TokenType::GREATER_EQUAL
+            | TokenType::LESS
+            | TokenType::LESS_EQUAL => Some(Self::Comparison),
+            TokenType::MINUS | TokenType::PLUS => Some(Self::Term),
+            TokenType::SLASH | TokenType::STAR => Some(Self::Factor),
+            _ => None,
+        }
+    }
+}
+
+struct Compiler {
+    tokens: Vec<Token>,
+    current: usize,
+    depth: usize,
+    builder: ChunkBuilder,
+}
+
+impl Compiler {
+    fn compile(mut self) -> Result<Chunk, LoxError> {
+        self.expression()?;
+        if *self.peek().token_type() != TokenType::EOF {
+            return Err(LoxError::parse(
+                ErrorCode::L0017,
+                self.peek(),
+                "Expect end of expression.",
+            ));
+        }
+
+        let end = self.peek().clone();
+        self.builder.emit_return(line(&end));
+        self.builder
+            .build()
+            .map_err(|error| LoxError::parse(ErrorCode::L0018, &end, &format!("{}", error)))
+    }
+
+    fn expression(&mut self) -> Result<(), LoxError> {
+        self.parse_precedence(Precedence::Equality)
+    }
+
+    fn parse_precedence(&mut self, precedence: Precedence) -> Result<(), LoxError> {
+        self.prefix()?;
+        while let Some(infix) = Precedence::of_infix(self.peek().token_type())
+            && precedence <= infix
+        {
+            self.binary(infix)?;
+        }
+        Ok(())
+    }
+
+    fn prefix(&mut self) -> Result<(), LoxError> {
+        let token = self.advance();
+        match token.token_type() {
+            TokenType::NUMBER => {
+                let Some(Object::Num(value)) = token.literal() else {
+                    unreachable!("the scanner gives every number a numeric literal");
+                };
+                self.builder.emit_constant(*value, line(&token));
+                Ok(())
+            }
+            TokenType::LEFT_PAREN => self.nested(&token, |compiler| {
+                compiler.expression()?;
+                compiler.consume(
+                    TokenType::RIGHT_PAREN,
+                    ErrorCode::L0004,
+                    "Expect ')' after expression.",
+                )
+            }),
+            TokenType::MINUS => self.nested(&token, |compiler| {
+                compiler.parse_precedence(Precedence::Unary)?;
+                compiler.builder.emit_op(OpCode::OP_NEGATE, line(&token));
+                Ok(())
+            }),
+            TokenType::STRING
+            | TokenType::INTERPOLATION
+            | TokenType::TRUE
+            | TokenType::FALSE
+            | TokenType::NIL
+            | TokenType::BANG => Err(unsupported(&token)),
+            _ => Err(LoxError::parse(
+                ErrorCode::L0003,
+                &token,
+                "Expect expression.",
+            )),
+        }
+    }
+
+    fn binary(&mut self, precedence: Precedence) -> Result<(), LoxError> {
+        let operator = self.advance();
+        let opcode = match operator.token_type() {
+            TokenType::PLUS => OpCode::OP_ADD,
+            TokenType::MINUS => OpCode::OP_SUBTRACT,
+            TokenType::STAR => OpCode::OP_MULTIPLY,
+            TokenType::SLASH => OpCode::OP_DIVIDE,
+            _ => return Err(unsupported(&operator)),
+        };
+        self.parse_precedence(precedence.next())?;
+        self.builder.emit_op(opcode, line(&operator));
+        Ok(())
+    }
+
+    /// Runs `compile` one nesting level deeper, failing instead once the
+    /// limit is reached so deep input can't exhaust the native stack.
+    fn nested(
+        &mut self,
+        token: &Token,
+        compile: impl FnOnce(&mut Self) -> Result<(), LoxError>,
+    ) -> Result<(), LoxError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(LoxError::parse(
+                ErrorCode::L0009,
+                token,
+                "Expression nested too deeply.",
+            ));
+        }
+        self.depth += 1;
+        let result = compile(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn consume(
+        &mut self,
+        token_type: TokenType,
+        code: ErrorCode,
+        message: &str,
+    ) -> Result<(), LoxError> {
+        if *self.peek().token_type() == token_type {
+            self.advance();
+            Ok(())
+        } else {
+            Err(LoxError::parse(code, self.peek(), message))
+        }
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.current]
+    }
+
+    /// The current token, moving past it unless it is the EOF.
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        if *token.token_type() != TokenType::EOF {
+            self.current += 1;
+        }
+        token
+    }
+}
+
+fn line(token: &Token) -> i32 {
+    i32::try_from(token.line()).unwrap_or(i32::MAX)
+}
+
+fn unsupported(token: &Token) -> LoxError {
+    LoxError::parse(
+        ErrorCode::L0018,
+        token,
+        "Not supported by rlox-bytecode yet.",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify::verify;
+
+    fn opcodes(chunk: &Chunk) -> Vec<OpCode> {
+        chunk
+            .instructions()
+            .map(|instruction| instruction.unwrap().opcode)
+            .collect()
+    }
+
+    fn error(source: &str) -> String {
+        let errors = compile(source).err().unwrap();
+        assert_eq!(errors.len(), 1);
+        errors[0].to_string()
+    }
+
+    #[test]
+    fn test_compile_arithmetic() {
+        let chunk = compile("-(1 + 2) * 3 - 4 / 2").unwrap();
+
+        assert_eq!(verify(&chunk), Ok(()));
+        assert_eq!(chunk.constants(), [1.0, 2.0, 3.0, 4.0, 2.0]);
+        assert_eq!(
+            opcodes(&chunk),
+            [
+                OpCode::OP_CONSTANT,
+                OpCode::OP_CONSTANT,
+                OpCode::OP_ADD,
+                OpCode::OP_NEGATE,
+                OpCode::OP_CONSTANT,
+                OpCode::OP_MULTIPLY,
+                OpCode::OP_CONSTANT,
+                OpCode::OP_CONSTANT,
+                OpCode::OP_DIVIDE,
+                OpCode::OP_SUBTRACT,
+                OpCode::OP_RETURN,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lines() {
+        let chunk = compile("1 +\n2").unwrap();
+
+        assert_eq!(chunk.line(0), Some(1));
+        assert_eq!(chunk.line(2), Some(2));
+        assert_eq!(chunk.line(4), Some(1));
+    }
+
+    #[test]
+    fn test_errors_match_rlox_ast() {
+        assert_eq!(error("1 +"), "[line 1] Error at end: Expect expression.");
+        assert_eq!(
+            error("(1 + 2"),
+            "[line 1] Error at end: Expect ')' after expression."
+        );
+        assert_eq!(error("@"), "[line 1] Error: Unexpected character.");
+        assert_eq!(
+            error(&format!("{}1{}", "(".repeat(300), ")".repeat(300))),
+            "[line 1] Error at '(': Expression nested too deeply."
+        );
+    }
+
+    #[test]
+    fn test_unsupported_and_trailing_input() {
+        assert_eq!(
+            error("1 2"),
+            "[line 1] Error at '2': Expect end of expression."
+        );
+        assert_eq!(
+            error("1 < 2"),
+            "[line 1] Error at '<': Not supported by rlox-bytecode yet."
+        );
+        assert_eq!(
+            error("\"a\""),
+            "[line 1] Error at '\"a\"': Not supported by rlox-bytecode yet."
+        );
+    }
+
+    #[test]
+    fn test_flat_chain_is_not_nesting() {
+        assert!(compile(&format!("{}1", "1 + ".repeat(255))).is_ok());
+        assert_eq!(
+            error(&format!("{}1", "1 + ".repeat(300))),
+            "[line 1] Error at end: Too many constants in one chunk."
+        );
+    }
+}
@@ -0,0 +1,145 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of rlox-bytecode
+
+use lox_syntax::{ErrorCode, ErrorKind, StackFrame, json};
+
+/// How errors are written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+    #[default]
+    Human,
+    /// One JSON object per line, for tools.
+    Json,
+}
+
+/// An error from reading or writing files, or from verifying or running a
+/// chunk, written with the same keys as `LoxError::to_json` so tools can
+/// read both interpreters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// [`ErrorKind::Io`] for files that can't be read, written or decoded,
+    /// [`ErrorKind::Runtime`] for chunks that fail verification or fault.
+    pub kind: ErrorKind,
+    pub code: ErrorCode,
+    pub message: String,
+    pub file: Option<String>,
+    /// Source line the failing instruction was compiled from, if known.
+    pub line: Option<usize>,
+    /// Offset of the failing instruction in the chunk's code, if known.
+    pub offset: Option<usize>,
+    /// Innermost frame first.
+    pub trace: Vec<StackFrame>,
+}
+
+impl Diagnostic {
+    pub fn new(kind: ErrorKind, code: ErrorCode, message: String, file: Option<String>) -> Self {
+        Self {
+            kind,
+            code,
+            message,
+            file,
+            line: None,
+            offset: None,
+            trace: Vec::new(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        // Bytecode has no columns; the span is the instruction's offset.
+        let span = self.offset.map_or("null".to_string(), |offset| {
+            format!("{{\"start\":{},\"end\":{}}}", offset, offset + 1)
+        });
+        let trace = self
+            .trace
+            .iter()
+            .map(StackFrame::to_json)
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "{{\"severity\":\"error\",\"kind\":\"{}\",\"code\":\"{}\",\"message\":{},\"file\":{},\"line\":{},\"column\":null,\"span\":{},\"help\":null,\"trace\":[{}]}}",
+            self.kind.name(),
+            self.code,
+            json::string(&self.message),
+            json::optional_string(self.file.as_deref()),
+            self.line
+                .map_or("null".to_string(), |line| line.to_string()),
+            span,
+            trace
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json() {
+        let diagnostic = Diagnostic {
+            line: Some(123),
+            offset: Some(2),
+            trace: vec![StackFrame {
+                function: None,
+                line: 123,
+            }],
+            ..Diagnostic::new(
+                ErrorKind::Runtime,
+                ErrorCode::L0020,
+                "Invalid bytecode at offset 2: stack underflow.".to_string(),
+                Some("out\\\"a\".loxc".to_string()),
+            )
+        };
+
+        assert_eq!(
+            diagnostic.to_json(),
+            "{\"severity\":\"error\",\"kind\":\"runtime\",\"code\":\"L0020\",\
+             \"message\":\"Invalid bytecode at offset 2: stack underflow.\",\
+             \"file\":\"out\\\\\\\"a\\\".loxc\",\"line\":123,\"column\":null,\
+             \"span\":{\"start\":2,\"end\":3},\"help\":null,\
+             \"trace\":[{\"function\":null,\"line\":123}]}"
+        );
+    }
+
+    #[test]
+    fn test_to_json_without_location() {
+        let diagnostic = Diagnostic::new(
+            ErrorKind::Io,
+            ErrorCode::L0019,
+            "Not a .loxc file (bad magic number).\n".to_string(),
+            None,
+        );
+
+        assert!(diagnostic.to_json().ends_with(
+            "\"kind\":\"io\",\"code\":\"L0019\",\"message\":\"Not a .loxc file (bad magic number).\\n\",\
+             \"file\":null,\"line\":null,\"column\":null,\"span\":null,\"help\":null,\"trace\":[]}"
+        ));
+    }
+
+    #[test]
+    fn test_same_keys_as_lox_error() {
+        let keys = |json: &str| {
+            json.split(['{', ','])
+                .filter_map(|field| field.split_once("\":"))
+                .map(|(key, _)| key.trim_start_matches('"').to_string())
+                .collect::<Vec<_>>()
+        };
+        let error = lox_syntax::LoxError::runtime_at(
+            ErrorCode::L0010,
+            lox_syntax::Span::new(0, 1, 1, 1),
+            "Stack overflow.",
+        );
+        let diagnostic = Diagnostic {
+            offset: Some(0),
+            ..Diagnostic::new(
+                ErrorKind::Runtime,
+                ErrorCode::L0020,
+                "Stack underflow.".to_string(),
+                None,
+            )
+        };
+
+        assert_eq!(keys(&diagnostic.to_json()), keys(&error.to_json(None)));
+    }
+}
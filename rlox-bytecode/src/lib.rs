@@ -3,12 +3,11 @@
 //
 // This file is part of rlox-bytecode
 
+pub mod builder;
 pub mod chunk;
+pub mod compiler;
+pub mod diagnostic;
+pub mod loxc;
 pub mod value;
-
-pub fn grow_capacity(capacity: i32) -> i32 {
-    match capacity {
-        ..=7 => 8,
-        _ => capacity * 2,
-    }
-}
+pub mod verify;
+pub mod vm;
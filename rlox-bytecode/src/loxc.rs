@@ -0,0 +1,297 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of rlox-bytecode
+
+//! The `.loxc` precompiled chunk format.
+//!
+//! All integers are little endian.
+//!
+//! ```text
+//! magic       4 bytes   "LOXC"
+//! version     u16       FORMAT_VERSION
+//! chunk:
+//!   code      u32 length, then that many bytes
+//!   lines     u32 run count, then (line: i32, length: u32) runs covering the code
+//!   constants u32 count, then one tagged entry each
+//! ```
+//!
+//! Each constant starts with a one byte tag. Numbers are the only values
+//! so far:
+//!
+//! ```text
+//! TAG_NUMBER    f64
+//! ```
+//!
+//! Function constants with their nested chunks will need a tag of their own
+//! once the VM has functions.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::chunk::Chunk;
+
+pub const MAGIC: &[u8; 4] = b"LOXC";
+pub const FORMAT_VERSION: u16 = 1;
+
+const TAG_NUMBER: u8 = 0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    BadMagic,
+    UnsupportedVersion(u16),
+    UnexpectedEof { offset: usize },
+    UnknownConstantTag { offset: usize, tag: u8 },
+    LineTableMismatch { code_len: usize, lines_len: usize },
+    TrailingBytes { offset: usize },
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadMagic => write!(f, "Not a .loxc file (bad magic number)."),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "Unsupported .loxc version {} (expected {}).",
+                version, FORMAT_VERSION
+            ),
+            Self::UnexpectedEof { offset } => {
+                write!(f, "Unexpected end of file at byte {}.", offset)
+            }
+            Self::UnknownConstantTag { offset, tag } => {
+                write!(f, "Unknown constant tag {} at byte {}.", tag, offset)
+            }
+            Self::LineTableMismatch {
+                code_len,
+                lines_len,
+            } => write!(
+                f,
+                "Line table covers {} bytes but the chunk has {} bytes of code.",
+                lines_len, code_len
+            ),
+            Self::TrailingBytes { offset } => {
+                write!(f, "Unexpected data after the chunk at byte {}.", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl Chunk {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        write_chunk(self, &mut out);
+        out
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Chunk, LoadError> {
+        let mut reader = Reader { bytes, offset: 0 };
+
+        if reader.take(MAGIC.len()).map_err(|_| LoadError::BadMagic)? != MAGIC {
+            return Err(LoadError::BadMagic);
+        }
+        let version = reader.u16()?;
+        if version != FORMAT_VERSION {
+            return Err(LoadError::UnsupportedVersion(version));
+        }
+
+        let chunk = read_chunk(&mut reader)?;
+        if reader.offset != bytes.len() {
+            return Err(LoadError::TrailingBytes {
+                offset: reader.offset,
+            });
+        }
+        Ok(chunk)
+    }
+}
+
+fn write_chunk(chunk: &Chunk, out: &mut Vec<u8>) {
+    let code = chunk.code();
+    out.extend_from_slice(&(code.len() as u32).to_le_bytes());
+    out.extend_from_slice(code);
+
+    let mut runs: Vec<(i32, u32)> = Vec::new();
+    for offset in 0..code.len() {
+        let line = chunk.line(offset).expect("Every byte of code has a line");
+        match runs.last_mut() {
+            Some((last, length)) if *last == line => *length += 1,
+            _ => runs.push((line, 1)),
+        }
+    }
+    out.extend_from_slice(&(runs.len() as u32).to_le_bytes());
+    for (line, length) in runs {
+        out.extend_from_slice(&line.to_le_bytes());
+        out.extend_from_slice(&length.to_le_bytes());
+    }
+
+    let constants = chunk.constants();
+    out.extend_from_slice(&(constants.len() as u32).to_le_bytes());
+    for constant in constants {
+        out.push(TAG_NUMBER);
+        out.extend_from_slice(&constant.to_le_bytes());
+    }
+}
+
+fn read_chunk(reader: &mut Reader) -> Result<Chunk, LoadError> {
+    let code_len = reader.u32()? as usize;
+    let code = reader.take(code_len)?;
+
+    let run_count = reader.u32()?;
+    let mut lines = Vec::new();
+    for _ in 0..run_count {
+        let line = reader.i32()?;
+        let length = reader.u32()? as usize;
+        if lines.len() + length > code_len {
+            return Err(LoadError::LineTableMismatch {
+                code_len,
+                lines_len: lines.len() + length,
+            });
+        }
+        lines.resize(lines.len() + length, line);
+    }
+    if lines.len() != code_len {
+        return Err(LoadError::LineTableMismatch {
+            code_len,
+            lines_len: lines.len(),
+        });
+    }
+
+    let mut chunk = Chunk::new();
+    for (byte, line) in code.iter().zip(lines) {
+        chunk.write_chunk(*byte, line);
+    }
+
+    let constant_count = reader.u32()?;
+    for _ in 0..constant_count {
+        let offset = reader.offset;
+        match reader.u8()? {
+            TAG_NUMBER => {
+                chunk.add_constant(reader.f64()?);
+            }
+            tag => return Err(LoadError::UnknownConstantTag { offset, tag }),
+        }
+    }
+
+    Ok(chunk)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], LoadError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(LoadError::UnexpectedEof {
+                offset: self.bytes.len(),
+            })?;
+        let bytes = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], LoadError> {
+        Ok(self.take(N)?.try_into().expect("take returns N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, LoadError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, LoadError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, LoadError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> Result<i32, LoadError> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> Result<f64, LoadError> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::OpCode;
+
+    fn test_chunk() -> Chunk {
+        let mut chunk = Chunk::new();
+        let constant = chunk.add_constant(1.2);
+        chunk.write_chunk(OpCode::OP_CONSTANT.value(), 123);
+        chunk.write_chunk(constant as u8, 123);
+        chunk.write_chunk(OpCode::OP_RETURN.value(), 124);
+        chunk
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let chunk = test_chunk();
+
+        let loaded = Chunk::deserialize(&chunk.serialize()).unwrap();
+
+        assert_eq!(loaded.code(), chunk.code());
+        assert_eq!(loaded.constants(), chunk.constants());
+        assert_eq!(loaded.line(0), Some(123));
+        assert_eq!(loaded.line(2), Some(124));
+    }
+
+    #[test]
+    fn test_bad_header() {
+        let mut bytes = test_chunk().serialize();
+        bytes[4] = 9;
+
+        assert_eq!(
+            Chunk::deserialize(&bytes).err(),
+            Some(LoadError::UnsupportedVersion(9))
+        );
+        assert_eq!(Chunk::deserialize(b"LOX").err(), Some(LoadError::BadMagic));
+        assert_eq!(
+            Chunk::deserialize(b"#!/usr/bin/env lox").err(),
+            Some(LoadError::BadMagic)
+        );
+    }
+
+    #[test]
+    fn test_every_truncation_is_an_error() {
+        let bytes = test_chunk().serialize();
+
+        for len in 0..bytes.len() {
+            assert!(Chunk::deserialize(&bytes[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_corrupted_contents() {
+        let mut bytes = test_chunk().serialize();
+        let tag_offset = bytes.len() - 9;
+        bytes[tag_offset] = 7;
+
+        assert_eq!(
+            Chunk::deserialize(&bytes).err(),
+            Some(LoadError::UnknownConstantTag {
+                offset: tag_offset,
+                tag: 7
+            })
+        );
+
+        let mut bytes = test_chunk().serialize();
+        bytes.push(0);
+
+        assert!(matches!(
+            Chunk::deserialize(&bytes),
+            Err(LoadError::TrailingBytes { .. })
+        ));
+    }
+}
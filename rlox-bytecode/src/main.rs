@@ -3,10 +3,61 @@
 //
 // This file is part of rlox-bytecode
 
+use std::io::Write;
+
+use lox_syntax::{ErrorCode, ErrorKind};
 use rlox::chunk::Chunk;
 use rlox::chunk::OpCode::{OP_CONSTANT, OP_RETURN};
+use rlox::compiler;
+use rlox::diagnostic::{Diagnostic, ErrorFormat};
+use rlox::verify::verify;
+use rlox::vm::{InterpretResult, VM};
+
+const USAGE: &str = "Usage: rlox-bytecode [options]
+       rlox-bytecode run <file.loxc> [options]
+       rlox-bytecode compile <file.lox> -o <file.loxc>
+       rlox-bytecode --explain <code>
+
+Options:
+  --trace, --trace=<file>     Trace execution to stderr or <file>
+  --error-format=human|json   How to print errors (default: human)";
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let [flag, code] = args.as_slice()
+        && flag == "--explain"
+    {
+        explain(code);
+    }
+
+    let mut vm = VM::new();
+    let mut format = ErrorFormat::Human;
+    let mut trace = None;
+    let mut rest = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--error-format=human" => format = ErrorFormat::Human,
+            "--error-format=json" => format = ErrorFormat::Json,
+            _ if arg.starts_with("--trace") => trace = Some(arg),
+            _ if arg.starts_with("--") => usage(),
+            _ => rest.push(arg),
+        }
+    }
+    // Opened once all options are read, so a failure respects --error-format.
+    if let Some(arg) = trace {
+        set_trace(&mut vm, format, &arg);
+    }
+    let args = rest;
+
+    match args.first().map(String::as_str) {
+        Some("run") => run(&mut vm, format, &args[1..]),
+        Some("compile") => compile(format, &args[1..]),
+        Some(_) => usage(),
+        None => demo(&mut vm, format),
+    }
+}
+
+fn demo(vm: &mut VM, format: ErrorFormat) {
     let mut chunk = Chunk::new();
 
     let constant = chunk.add_constant(1.2);
@@ -15,4 +66,179 @@ fn main() {
 
     chunk.write_chunk(OP_RETURN.value(), 123);
     chunk.disassemble_chunk("test chunk");
+    interpret(vm, &chunk, format, None);
+}
+
+fn run(vm: &mut VM, format: ErrorFormat, args: &[String]) {
+    let [path] = args else {
+        usage();
+    };
+
+    let bytes = std::fs::read(path).unwrap_or_else(|error| {
+        let human = format!("Could not read \"{}\": {}", path, error);
+        fail(
+            format,
+            &human,
+            &io_error(ErrorCode::L0019, &human, path),
+            66,
+        )
+    });
+    let chunk = Chunk::deserialize(&bytes).unwrap_or_else(|error| {
+        let human = format!("Could not load \"{}\": {}", path, error);
+        fail(
+            format,
+            &human,
+            &io_error(ErrorCode::L0019, &human, path),
+            65,
+        )
+    });
+    if let Err(error) = verify(&chunk) {
+        let diagnostic = Diagnostic {
+            line: chunk
+                .line(error.offset())
+                .and_then(|line| usize::try_from(line).ok()),
+            offset: Some(error.offset()),
+            ..Diagnostic::new(
+                ErrorKind::Runtime,
+                ErrorCode::L0020,
+                error.to_string(),
+                Some(path.clone()),
+            )
+        };
+        let human = format!("Could not load \"{}\": {}", path, error);
+        fail(format, &human, &diagnostic, 65);
+    }
+
+    interpret(vm, &chunk, format, Some(path));
+}
+
+fn interpret(vm: &mut VM, chunk: &Chunk, format: ErrorFormat, path: Option<&String>) {
+    let result = vm.interpret(chunk);
+    // The exits below skip the VM's destructor, so a buffered trace file
+    // would lose its end, which is the part that explains the error.
+    let _ = vm.flush_trace();
+    match result {
+        InterpretResult::INTERPRET_OK => {}
+        InterpretResult::INTERPRET_COMPILE_ERROR => std::process::exit(65),
+        InterpretResult::INTERPRET_RUNTIME_ERROR => {
+            let Some(error) = vm.last_error() else {
+                std::process::exit(70);
+            };
+            let diagnostic = Diagnostic {
+                line: error.trace.first().map(|frame| frame.line),
+                offset: Some(error.offset),
+                trace: error.trace.clone(),
+                ..Diagnostic::new(
+                    ErrorKind::Runtime,
+                    ErrorCode::L0020,
+                    error.message.clone(),
+                    path.cloned(),
+                )
+            };
+            fail(format, &error.to_string(), &diagnostic, 70);
+        }
+    }
+}
+
+/// Prints `diagnostic` (or `human` in the default format) and exits with `code`.
+fn fail(format: ErrorFormat, human: &str, diagnostic: &Diagnostic, code: i32) -> ! {
+    match format {
+        ErrorFormat::Human => eprintln!("{}", human),
+        ErrorFormat::Json => eprintln!("{}", diagnostic.to_json()),
+    }
+    std::process::exit(code);
+}
+
+/// A diagnostic for a file that couldn't be read, written or decoded.
+fn io_error(code: ErrorCode, message: &str, path: &str) -> Diagnostic {
+    Diagnostic::new(
+        ErrorKind::Io,
+        code,
+        message.to_string(),
+        Some(path.to_string()),
+    )
+}
+
+fn compile(format: ErrorFormat, args: &[String]) {
+    let [path, flag, output] = args else {
+        usage();
+    };
+    if flag != "-o" {
+        usage();
+    }
+
+    let source = std::fs::read_to_string(path).unwrap_or_else(|error| {
+        let human = format!("Could not read \"{}\": {}", path, error);
+        fail(
+            format,
+            &human,
+            &io_error(ErrorCode::L0019, &human, path),
+            66,
+        )
+    });
+
+    // Compile errors are `LoxError`s from the shared scanner and error
+    // types, so they look exactly like the ones rlox-ast reports.
+    let chunk = compiler::compile(&source).unwrap_or_else(|errors| {
+        for error in errors {
+            match format {
+                ErrorFormat::Human => eprintln!("{}", error.render(&source, "rlox-bytecode")),
+                ErrorFormat::Json => eprintln!("{}", error.to_json(Some(path))),
+            }
+        }
+        std::process::exit(65);
+    });
+
+    if let Err(error) = std::fs::write(output, chunk.serialize()) {
+        let human = format!("Could not write \"{}\": {}", output, error);
+        fail(
+            format,
+            &human,
+            &io_error(ErrorCode::L0022, &human, output),
+            74,
+        );
+    }
+}
+
+fn set_trace(vm: &mut VM, format: ErrorFormat, arg: &str) {
+    match arg {
+        "--trace" => vm.set_trace(Some(Box::new(std::io::stderr()))),
+        _ if arg.starts_with("--trace=") => {
+            let path = &arg["--trace=".len()..];
+            match std::fs::File::create(path) {
+                Ok(file) => {
+                    let sink: Box<dyn Write> = Box::new(std::io::BufWriter::new(file));
+                    vm.set_trace(Some(sink));
+                }
+                Err(error) => {
+                    let human = format!("Could not open trace file \"{}\": {}", path, error);
+                    fail(
+                        format,
+                        &human,
+                        &io_error(ErrorCode::L0022, &human, path),
+                        74,
+                    );
+                }
+            }
+        }
+        _ => usage(),
+    }
+}
+
+fn explain(name: &str) -> ! {
+    match ErrorCode::from_name(name) {
+        Some(code) => {
+            print!("{}", code.explain());
+            std::process::exit(0);
+        }
+        None => {
+            eprintln!("\"{}\" is not a known error code.", name);
+            std::process::exit(64);
+        }
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    std::process::exit(64);
 }
@@ -0,0 +1,286 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of rlox-bytecode
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::chunk::{Chunk, DecodeError, Instruction, OpCode, Operand};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    Decode(DecodeError),
+    JumpOutOfChunk {
+        offset: usize,
+        target: usize,
+    },
+    JumpIntoInstruction {
+        offset: usize,
+        target: usize,
+    },
+    StackUnderflow {
+        offset: usize,
+    },
+    InconsistentStack {
+        offset: usize,
+        expected: usize,
+        found: usize,
+    },
+    FallsOffEnd {
+        offset: usize,
+    },
+}
+
+impl VerifyError {
+    /// Offset of the instruction the problem was found at.
+    pub fn offset(&self) -> usize {
+        match self {
+            Self::Decode(error) => error.offset(),
+            Self::JumpOutOfChunk { offset, .. }
+            | Self::JumpIntoInstruction { offset, .. }
+            | Self::StackUnderflow { offset }
+            | Self::InconsistentStack { offset, .. }
+            | Self::FallsOffEnd { offset } => *offset,
+        }
+    }
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid bytecode at offset {}: ", self.offset())?;
+        match self {
+            Self::Decode(error) => write!(f, "{}.", error),
+            Self::JumpOutOfChunk { target, .. } => {
+                write!(f, "jump to {} is outside the chunk.", target)
+            }
+            Self::JumpIntoInstruction { target, .. } => {
+                write!(f, "jump to {} lands inside an instruction.", target)
+            }
+            Self::StackUnderflow { .. } => write!(f, "pops from an empty stack."),
+            Self::InconsistentStack {
+                expected, found, ..
+            } => write!(
+                f,
+                "stack depth is {} on one path and {} on another.",
+                expected, found
+            ),
+            Self::FallsOffEnd { .. } => {
+                write!(f, "execution runs past the end of the chunk.")
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+impl From<DecodeError> for VerifyError {
+    fn from(error: DecodeError) -> Self {
+        Self::Decode(error)
+    }
+}
+
+/// Checks that `chunk` can run on the VM without reading out of bounds.
+///
+/// Every instruction must decode, every jump must land on the start of an
+/// instruction, and each reachable instruction must be reached with the same
+/// stack depth on every path, which must be deep enough for what it pops.
+pub fn verify(chunk: &Chunk) -> Result<(), VerifyError> {
+    let instructions = chunk.instructions().collect::<Result<Vec<_>, _>>()?;
+
+    // Index of the instruction starting at each offset, if one does.
+    let mut starts = vec![None; chunk.len()];
+    for (index, instruction) in instructions.iter().enumerate() {
+        starts[instruction.offset] = Some(index);
+    }
+
+    for instruction in &instructions {
+        if let Some(target) = jump_target(instruction) {
+            if target >= chunk.len() {
+                return Err(VerifyError::JumpOutOfChunk {
+                    offset: instruction.offset,
+                    target,
+                });
+            }
+            if starts[target].is_none() {
+                return Err(VerifyError::JumpIntoInstruction {
+                    offset: instruction.offset,
+                    target,
+                });
+            }
+        }
+    }
+
+    if instructions.is_empty() {
+        return Err(VerifyError::FallsOffEnd { offset: 0 });
+    }
+
+    let mut depths: Vec<Option<usize>> = vec![None; instructions.len()];
+    let mut worklist = vec![(0, 0)];
+    while let Some((index, depth)) = worklist.pop() {
+        let instruction = &instructions[index];
+        match depths[index] {
+            Some(expected) if expected != depth => {
+                return Err(VerifyError::InconsistentStack {
+                    offset: instruction.offset,
+                    expected,
+                    found: depth,
+                });
+            }
+            Some(_) => continue,
+            None => depths[index] = Some(depth),
+        }
+
+        let (pops, pushes) = stack_effect(instruction.opcode);
+        let depth = depth.checked_sub(pops).ok_or(VerifyError::StackUnderflow {
+            offset: instruction.offset,
+        })? + pushes;
+
+        let next = match instruction.opcode {
+            OpCode::OP_RETURN => continue,
+            OpCode::OP_JUMP | OpCode::OP_LOOP => jump_target(instruction),
+            OpCode::OP_CONSTANT
+            | OpCode::OP_NEGATE
+            | OpCode::OP_ADD
+            | OpCode::OP_SUBTRACT
+            | OpCode::OP_MULTIPLY
+            | OpCode::OP_DIVIDE => Some(instruction.next_offset()),
+        };
+        if let Some(next) = next {
+            match starts.get(next) {
+                Some(Some(next)) => worklist.push((*next, depth)),
+                _ => {
+                    return Err(VerifyError::FallsOffEnd {
+                        offset: instruction.offset,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn jump_target(instruction: &Instruction) -> Option<usize> {
+    match instruction.operands.as_slice() {
+        [Operand::Jump { target, .. }] => Some(*target),
+        _ => None,
+    }
+}
+
+/// How many values an instruction pops and then pushes.
+fn stack_effect(opcode: OpCode) -> (usize, usize) {
+    match opcode {
+        OpCode::OP_CONSTANT => (0, 1),
+        OpCode::OP_RETURN => (1, 0),
+        OpCode::OP_JUMP | OpCode::OP_LOOP => (0, 0),
+        OpCode::OP_NEGATE => (1, 1),
+        OpCode::OP_ADD | OpCode::OP_SUBTRACT | OpCode::OP_MULTIPLY | OpCode::OP_DIVIDE => (2, 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ChunkBuilder;
+
+    #[test]
+    fn test_valid_chunk() {
+        let mut builder = ChunkBuilder::new();
+        let end = builder.new_label();
+        builder
+            .emit_constant(1.2, 1)
+            .emit_jump(end, 1)
+            .emit_return(2)
+            .bind(end)
+            .emit_return(3);
+
+        assert_eq!(verify(&builder.build().unwrap()), Ok(()));
+    }
+
+    #[test]
+    fn test_undecodable_chunk() {
+        let mut chunk = Chunk::new();
+        chunk.write_chunk(OpCode::OP_CONSTANT.value(), 1);
+        chunk.write_chunk(3, 1);
+
+        assert_eq!(
+            verify(&chunk),
+            Err(VerifyError::Decode(DecodeError::ConstantOutOfRange {
+                offset: 0,
+                index: 3
+            }))
+        );
+    }
+
+    #[test]
+    fn test_bad_jump_targets() {
+        let mut chunk = Chunk::new();
+        chunk.add_constant(1.0);
+        chunk.write_chunk(OpCode::OP_CONSTANT.value(), 1);
+        chunk.write_chunk(0, 1);
+        chunk.write_chunk(OpCode::OP_LOOP.value(), 1);
+        chunk.write_chunk(0, 1);
+        chunk.write_chunk(4, 1);
+
+        assert_eq!(
+            verify(&chunk),
+            Err(VerifyError::JumpIntoInstruction {
+                offset: 2,
+                target: 1
+            })
+        );
+
+        let mut chunk = Chunk::new();
+        chunk.write_chunk(OpCode::OP_JUMP.value(), 1);
+        chunk.write_chunk(0, 1);
+        chunk.write_chunk(0, 1);
+
+        assert_eq!(
+            verify(&chunk),
+            Err(VerifyError::JumpOutOfChunk {
+                offset: 0,
+                target: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_stack_depth() {
+        let mut builder = ChunkBuilder::new();
+        builder.emit_return(1);
+        assert_eq!(
+            verify(&builder.build().unwrap()),
+            Err(VerifyError::StackUnderflow { offset: 0 })
+        );
+
+        let mut builder = ChunkBuilder::new();
+        let start = builder.new_label();
+        builder
+            .bind(start)
+            .emit_constant(1.0, 1)
+            .emit_loop(start, 1);
+        assert_eq!(
+            verify(&builder.build().unwrap()),
+            Err(VerifyError::InconsistentStack {
+                offset: 0,
+                expected: 0,
+                found: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_falls_off_end() {
+        let mut builder = ChunkBuilder::new();
+        builder.emit_constant(1.0, 1);
+
+        assert_eq!(
+            verify(&builder.build().unwrap()),
+            Err(VerifyError::FallsOffEnd { offset: 0 })
+        );
+        assert_eq!(
+            verify(&Chunk::new()),
+            Err(VerifyError::FallsOffEnd { offset: 0 })
+        );
+    }
+}
@@ -0,0 +1,314 @@
+// SPDX-FileCopyrightText: 2024 John Irle
+// SPDX-License-Identifier: MIT
+//
+// This file is part of rlox-bytecode
+
+use std::fmt::{self, Display, Formatter};
+use std::io::Write;
+
+use lox_syntax::StackFrame;
+
+use crate::chunk::{Chunk, OpCode, Operand};
+use crate::value::Value;
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterpretResult {
+    INTERPRET_OK,
+    INTERPRET_COMPILE_ERROR,
+    INTERPRET_RUNTIME_ERROR,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeError {
+    pub message: String,
+    /// Offset of the failing instruction in the chunk's code.
+    pub offset: usize,
+    /// Innermost frame first.
+    pub trace: Vec<StackFrame>,
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        for frame in &self.trace {
+            write!(f, "\n{}", frame)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+pub struct VM {
+    ip: usize,
+    stack: Vec<Value>,
+    trace: Option<Box<dyn Write>>,
+    error: Option<RuntimeError>,
+}
+
+impl VM {
+    pub fn new() -> Self {
+        let trace: Option<Box<dyn Write>> = if cfg!(feature = "trace_execution") {
+            Some(Box::new(std::io::stderr()))
+        } else {
+            None
+        };
+
+        Self {
+            ip: 0,
+            stack: Vec::new(),
+            trace,
+            error: None,
+        }
+    }
+
+    /// Before each instruction, write the value stack and the disassembled
+    /// instruction to `sink`. Pass `None` to turn tracing off.
+    pub fn set_trace(&mut self, sink: Option<Box<dyn Write>>) {
+        self.trace = sink;
+    }
+
+    /// Flushes the trace sink, if there is one. Call this before exiting
+    /// with `std::process::exit`, which skips the destructor that would.
+    pub fn flush_trace(&mut self) -> std::io::Result<()> {
+        match self.trace.as_mut() {
+            Some(trace) => trace.flush(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn interpret(&mut self, chunk: &Chunk) -> InterpretResult {
+        self.ip = 0;
+        self.stack.clear();
+        self.error = None;
+        self.run(chunk)
+    }
+
+    /// The error that stopped the last call to [`VM::interpret`], if any.
+    pub fn last_error(&self) -> Option<&RuntimeError> {
+        self.error.as_ref()
+    }
+
+    fn run(&mut self, chunk: &Chunk) -> InterpretResult {
+        loop {
+            if self.trace.is_some() {
+                self.trace_instruction(chunk);
+            }
+
+            let offset = self.ip;
+            if offset >= chunk.len() {
+                return self.runtime_error(
+                    chunk,
+                    offset,
+                    "Reached the end of the chunk without returning.",
+                );
+            }
+            let instruction = match chunk.decode(offset) {
+                Ok(instruction) => instruction,
+                Err(error) => return self.runtime_error(chunk, offset, &error.to_string()),
+            };
+            self.ip = instruction.next_offset();
+
+            match (instruction.opcode, instruction.operands.as_slice()) {
+                (OpCode::OP_CONSTANT, [Operand::Constant { value, .. }]) => self.push(*value),
+                (OpCode::OP_JUMP | OpCode::OP_LOOP, [Operand::Jump { target, .. }]) => {
+                    self.ip = *target
+                }
+                (OpCode::OP_NEGATE, _) => {
+                    let Some(value) = self.pop() else {
+                        return self.runtime_error(chunk, offset, "Stack underflow.");
+                    };
+                    self.push(-value);
+                }
+                (
+                    OpCode::OP_ADD | OpCode::OP_SUBTRACT | OpCode::OP_MULTIPLY | OpCode::OP_DIVIDE,
+                    _,
+                ) => {
+                    let (Some(b), Some(a)) = (self.pop(), self.pop()) else {
+                        return self.runtime_error(chunk, offset, "Stack underflow.");
+                    };
+                    self.push(match instruction.opcode {
+                        OpCode::OP_ADD => a + b,
+                        OpCode::OP_SUBTRACT => a - b,
+                        OpCode::OP_MULTIPLY => a * b,
+                        _ => a / b,
+                    });
+                }
+                (OpCode::OP_RETURN, _) => {
+                    let Some(value) = self.pop() else {
+                        return self.runtime_error(chunk, offset, "Stack underflow.");
+                    };
+                    println!("{}", value);
+                    return InterpretResult::INTERPRET_OK;
+                }
+                _ => unreachable!("decode always yields the operands of its opcode"),
+            }
+        }
+    }
+
+    fn trace_instruction(&mut self, chunk: &Chunk) {
+        let mut line = String::from("          ");
+        for value in &self.stack {
+            line.push_str(&format!("[ {} ]", value));
+        }
+        line.push('\n');
+        if self.ip < chunk.len() {
+            chunk
+                .disassemble_instruction(self.ip, &mut line)
+                .expect("Writing to a String cannot fail");
+        }
+
+        if let Some(trace) = self.trace.as_mut() {
+            // Tracing is a debugging aid; a broken sink shouldn't stop the program.
+            let _ = trace.write_all(line.as_bytes());
+        }
+    }
+
+    fn push(&mut self, value: Value) {
+        self.stack.push(value);
+    }
+
+    fn pop(&mut self) -> Option<Value> {
+        self.stack.pop()
+    }
+
+    fn runtime_error(&mut self, chunk: &Chunk, offset: usize, message: &str) -> InterpretResult {
+        // Only top-level code runs until the VM has call frames.
+        let trace = chunk
+            .line(offset.min(chunk.len().saturating_sub(1)))
+            .map(|line| StackFrame {
+                function: None,
+                line: usize::try_from(line).unwrap_or(0),
+            })
+            .into_iter()
+            .collect();
+        self.error = Some(RuntimeError {
+            message: message.to_string(),
+            offset,
+            trace,
+        });
+        self.stack.clear();
+        InterpretResult::INTERPRET_RUNTIME_ERROR
+    }
+}
+
+impl Default for VM {
+    fn default() -> Self {
+        VM::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::builder::ChunkBuilder;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_trace_execution() {
+        let mut chunk = Chunk::new();
+        let constant = chunk.add_constant(1.2);
+        chunk.write_chunk(OpCode::OP_CONSTANT.value(), 123);
+        chunk.write_chunk(constant as u8, 123);
+        chunk.write_chunk(OpCode::OP_RETURN.value(), 123);
+
+        let buffer = SharedBuffer::default();
+        let mut vm = VM::new();
+        vm.set_trace(Some(Box::new(buffer.clone())));
+
+        assert_eq!(vm.interpret(&chunk), InterpretResult::INTERPRET_OK);
+        assert_eq!(
+            String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap(),
+            "          \n\
+             0000  123 OP_CONSTANT         0 '1.2'\n          \
+             [ 1.2 ]\n\
+             0002    | OP_RETURN\n"
+        );
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut builder = ChunkBuilder::new();
+        builder
+            .emit_constant(3.0, 1)
+            .emit_constant(4.0, 1)
+            .emit_op(OpCode::OP_SUBTRACT, 1)
+            .emit_op(OpCode::OP_NEGATE, 1)
+            .emit_constant(2.0, 1)
+            .emit_op(OpCode::OP_DIVIDE, 1)
+            .emit_return(1);
+        let chunk = builder.build().unwrap();
+
+        let buffer = SharedBuffer::default();
+        let mut vm = VM::new();
+        vm.set_trace(Some(Box::new(buffer.clone())));
+
+        assert_eq!(vm.interpret(&chunk), InterpretResult::INTERPRET_OK);
+        let trace = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(
+            trace.ends_with("[ 0.5 ]\n0009    | OP_RETURN\n"),
+            "{}",
+            trace
+        );
+    }
+
+    #[test]
+    fn test_flush_trace() {
+        let mut chunk = Chunk::new();
+        chunk.write_chunk(OpCode::OP_RETURN.value(), 1);
+
+        let buffer = SharedBuffer::default();
+        let mut vm = VM::new();
+        vm.set_trace(Some(Box::new(std::io::BufWriter::new(buffer.clone()))));
+
+        assert_eq!(
+            vm.interpret(&chunk),
+            InterpretResult::INTERPRET_RUNTIME_ERROR
+        );
+        assert!(buffer.0.lock().unwrap().is_empty());
+        vm.flush_trace().unwrap();
+        assert_eq!(
+            String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap(),
+            "          \n0000    1 OP_RETURN\n"
+        );
+    }
+
+    #[test]
+    fn test_return_with_empty_stack() {
+        let mut chunk = Chunk::new();
+        chunk.write_chunk(OpCode::OP_RETURN.value(), 1);
+
+        let mut vm = VM::new();
+        vm.set_trace(None);
+
+        assert_eq!(
+            vm.interpret(&chunk),
+            InterpretResult::INTERPRET_RUNTIME_ERROR
+        );
+        let error = vm.last_error().unwrap();
+        assert_eq!(error.offset, 0);
+        assert_eq!(
+            error.trace,
+            vec![StackFrame {
+                function: None,
+                line: 1
+            }]
+        );
+        assert_eq!(error.to_string(), "Stack underflow.\n[line 1] in script");
+    }
+}